@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::io::BufReader;
+use rodio::{Decoder, OutputStream, Sink, Source};
+
+pub const MUSIC_FILE: &str = "assets/laberinto.mp3";
+pub const BUMP_SOUND: &str = "assets/bump.wav";
+pub const PICKUP_SOUND: &str = "assets/pickup.wav";
+
+// Música de fondo y efectos, cada uno en su propio sink para regular el volumen por separado.
+// Los `OutputStream` deben vivir mientras suene algo, por eso se guardan aquí.
+pub struct Audio {
+    _music_stream: OutputStream,
+    music: Sink,
+    _fx_stream: OutputStream,
+    fx: Sink,
+}
+
+impl Audio {
+    pub fn new(music_volume: u32, sfx_volume: u32) -> Audio {
+        // Música de fondo
+        let (music_stream, music_handle) = OutputStream::try_default().unwrap();
+        let music = Sink::try_new(&music_handle).unwrap();
+        let file = File::open(MUSIC_FILE).unwrap();
+        let source = Decoder::new(BufReader::new(file)).unwrap().repeat_infinite();
+        music.append(source);
+        music.play();
+
+        // Efectos de sonido
+        let (fx_stream, fx_handle) = OutputStream::try_default().unwrap();
+        let fx = Sink::try_new(&fx_handle).unwrap();
+
+        let audio = Audio {
+            _music_stream: music_stream,
+            music,
+            _fx_stream: fx_stream,
+            fx,
+        };
+        audio.set_music_volume(music_volume);
+        audio.set_sfx_volume(sfx_volume);
+        audio
+    }
+
+    // Volumen de 0 a 100
+    pub fn set_music_volume(&self, volume: u32) {
+        self.music.set_volume(volume.min(100) as f32 / 100.0);
+    }
+
+    pub fn set_sfx_volume(&self, volume: u32) {
+        self.fx.set_volume(volume.min(100) as f32 / 100.0);
+    }
+
+    // Reproduce un efecto; si el archivo falta o no se puede decodificar se ignora
+    pub fn play_effect(&self, path: &str) {
+        if let Ok(file) = File::open(path)
+            && let Ok(source) = Decoder::new(BufReader::new(file))
+        {
+            self.fx.append(source);
+        }
+    }
+
+    pub fn stop(&self) {
+        self.music.stop();
+    }
+}
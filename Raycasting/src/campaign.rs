@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+use crate::maze::Maze;
+
+// Lista de niveles de la campaña, uno por línea, con rutas relativas al propio archivo
+pub const CAMPAIGN_FILE: &str = "assets/levels/campania.txt";
+
+// Se guarda en el directorio de trabajo, como los ajustes y las puntuaciones
+pub const PROGRESS_FILE: &str = "progreso.txt";
+
+pub struct CampaignLevel {
+    pub path: String,
+    pub name: String,
+}
+
+// Niveles desbloqueados y nivel por el que seguir al volver a abrir el juego
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    // Cuántos niveles se pueden jugar, contando desde el primero (al menos 1)
+    pub unlocked: usize,
+    // Índice del nivel por el que se continúa
+    pub current: usize,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self { unlocked: 1, current: 0 }
+    }
+}
+
+impl Progress {
+    // Si el archivo no existe se empieza desde el primer nivel
+    pub fn load(path: &str) -> Progress {
+        if !Path::new(path).exists() {
+            return Progress::default();
+        }
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let (progress, warnings) = Progress::parse(&text);
+                for warning in warnings {
+                    eprintln!("{}: {}", path, warning);
+                }
+                progress
+            }
+            Err(err) => {
+                eprintln!("No se pudo leer el progreso de {}: {}", path, err);
+                Progress::default()
+            }
+        }
+    }
+
+    // Mismo formato `clave = valor` que los ajustes; en el archivo los niveles se cuentan desde 1
+    pub fn parse(text: &str) -> (Progress, Vec<String>) {
+        let mut progress = Progress::default();
+        let mut warnings = Vec::new();
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warnings.push(format!("línea {}: se esperaba `clave = valor`", line_number));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let Some(number) = value.parse::<usize>().ok().filter(|&n| n >= 1) else {
+                warnings.push(format!("línea {}: valor inválido `{}` para `{}`", line_number, value, key));
+                continue;
+            };
+            match key {
+                "desbloqueados" => progress.unlocked = number,
+                "nivel" => progress.current = number - 1,
+                _ => warnings.push(format!("línea {}: clave desconocida `{}`", line_number, key)),
+            }
+        }
+        (progress, warnings)
+    }
+
+    pub fn to_progress_string(self) -> String {
+        let mut text = String::from("# Progreso de la campaña de Laberinto Raycasting\n");
+        text += &format!("desbloqueados = {}\n", self.unlocked);
+        text += &format!("nivel = {}\n", self.current + 1);
+        text
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_progress_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+    pub progress: Progress,
+    // Índice del nivel que se está jugando
+    pub playing: usize,
+}
+
+impl Campaign {
+    // Lee la lista y carga cada nivel para validarlo y conocer su nombre; un nivel roto
+    // es un error, igual que al abrir un nivel suelto
+    pub fn load(path: &str, progress: Progress) -> Result<Campaign, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+        let mut levels = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let level_path = directory.join(line).to_string_lossy().into_owned();
+            let maze = Maze::load(&level_path).map_err(|err| err.to_string())?;
+            levels.push(CampaignLevel { path: level_path, name: maze.name });
+        }
+        if levels.is_empty() {
+            return Err(format!("{}: la campaña no tiene niveles", path));
+        }
+
+        // Un progreso de una campaña más larga no debe apuntar fuera de esta
+        let unlocked = progress.unlocked.clamp(1, levels.len());
+        let current = progress.current.min(unlocked - 1);
+        Ok(Campaign {
+            levels,
+            progress: Progress { unlocked, current },
+            playing: current,
+        })
+    }
+
+    pub fn load_level(&self, index: usize) -> Result<Maze, String> {
+        Maze::load(&self.levels[index].path).map_err(|err| err.to_string())
+    }
+
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index < self.progress.unlocked
+    }
+
+    pub fn next_level(&self) -> Option<usize> {
+        Some(self.playing + 1).filter(|&next| next < self.levels.len())
+    }
+
+    // Completar un nivel desbloquea el siguiente, que pasa a ser por el que se continúa
+    pub fn complete_level(&mut self) {
+        if let Some(next) = self.next_level() {
+            self.progress.unlocked = self.progress.unlocked.max(next + 1);
+            self.progress.current = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Campaña temporal con los dos primeros niveles de la de verdad
+    fn short_campaign(name: &str) -> String {
+        let levels = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/levels");
+        let path = std::env::temp_dir().join(format!("raycasting_{}_{}.txt", std::process::id(), name));
+        fs::write(&path, format!("# Dos niveles\n{}\n\n{}\n", levels.join("clasico.txt").display(), levels.join("pasillos.txt").display()))
+            .unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn progress_levels_count_from_one() {
+        let (progress, warnings) = Progress::parse("desbloqueados = 3\nnivel = 2\n");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(progress, Progress { unlocked: 3, current: 1 });
+        assert_eq!(Progress::parse(&progress.to_progress_string()), (progress, Vec::new()));
+
+        // `nivel = 0` no existe: se avisa y se sigue por el primero
+        let (progress, warnings) = Progress::parse("nivel = 0\ndesbloqueados = 0\nrecord = 5\n");
+        assert_eq!(progress, Progress::default());
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].starts_with("línea 1:") && warnings[0].contains("valor inválido"));
+        assert!(warnings[2].contains("clave desconocida"));
+    }
+
+    #[test]
+    fn progress_from_a_longer_campaign_is_clamped() {
+        let path = short_campaign("campania_corta");
+        let result = Campaign::load(&path, Progress { unlocked: 6, current: 4 });
+        fs::remove_file(&path).unwrap();
+        let mut campaign = result.unwrap();
+        assert_eq!(campaign.levels.len(), 2);
+        assert_eq!(campaign.progress, Progress { unlocked: 2, current: 1 });
+        assert_eq!(campaign.playing, 1);
+        assert!(campaign.is_unlocked(1) && !campaign.is_unlocked(2));
+
+        // El último nivel no tiene siguiente: completarlo no cambia el progreso
+        assert_eq!(campaign.next_level(), None);
+        campaign.complete_level();
+        assert_eq!(campaign.progress, Progress { unlocked: 2, current: 1 });
+    }
+
+    #[test]
+    fn completing_a_level_unlocks_the_next() {
+        let path = short_campaign("campania_nueva");
+        let result = Campaign::load(&path, Progress::default());
+        fs::remove_file(&path).unwrap();
+        let mut campaign = result.unwrap();
+        assert!(!campaign.is_unlocked(1));
+        campaign.complete_level();
+        assert_eq!(campaign.progress, Progress { unlocked: 2, current: 1 });
+    }
+}
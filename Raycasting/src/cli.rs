@@ -0,0 +1,431 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::framebuffer::Framebuffer;
+use crate::generator::{generate, Algorithm};
+use crate::maze::{Maze, MazeError, DEFAULT_LEVEL};
+use crate::player::Player;
+use crate::enemies::Enemy;
+use crate::renderer::{default_threads, render_scene, SceneView, Sprite};
+use crate::textures::{TextureSet, TEXTURE_DIR};
+use crate::validator::validate;
+
+pub const USAGE: &str = "Uso:
+  Raycasting                 (campaña)
+  Raycasting nivel.txt
+  Raycasting --generar ALGORITMO [--semilla N] [--tamano ANCHOxALTO] [--guardar nivel.txt]
+  Raycasting --comprobar [nivel.txt ...] [--generar ALGORITMO ...]
+  Raycasting --benchmark [FOTOGRAMAS] [nivel.txt] [--hilos N]
+  Raycasting --render imagen.png|imagen.ppm [nivel.txt] [--posicion FILA,COL] [--angulo GRADOS]
+             [--resolucion ANCHOxALTO] [--comparar referencia.ppm]
+
+Opciones comunes: --hilos N (hilos de render, por defecto todos los núcleos)
+
+Algoritmos: backtracker, prim, kruskal, wilson, salas";
+
+#[derive(Debug, Clone)]
+pub enum LevelSource {
+    File(String),
+    Generated {
+        algorithm: Algorithm,
+        seed: u64,
+        width: usize,
+        height: usize,
+    },
+}
+
+pub enum Command {
+    Play {
+        // Sin nivel en la línea de comandos se juega la campaña
+        level: Option<LevelSource>,
+        save_path: Option<String>,
+        threads: usize,
+    },
+    Check(Vec<LevelSource>),
+    Benchmark {
+        level: LevelSource,
+        frames: usize,
+        threads: usize,
+    },
+    Render(RenderOptions),
+}
+
+// Qué dibuja `--render`, desde dónde y con qué imagen de referencia se compara
+pub struct RenderOptions {
+    pub level: LevelSource,
+    pub output: String,
+    // Posición en celdas (fila, columna); 1.5,1.5 es el centro de la celda (1, 1)
+    pub position: Option<(f32, f32)>,
+    pub angle: Option<f32>,
+    pub size: (usize, usize),
+    pub reference: Option<String>,
+    pub threads: usize,
+}
+
+// Diferencia máxima por canal que se acepta al comparar con una imagen de referencia
+const REFERENCE_TOLERANCE: u8 = 2;
+
+// Píxeles como mucho de `--resolucion` (8K), para no intentar reservar un framebuffer imposible
+const MAX_RESOLUTION_PIXELS: usize = 7680 * 4320;
+
+impl LevelSource {
+    pub fn load(&self) -> Result<Maze, String> {
+        match self {
+            LevelSource::File(path) => Maze::load(path).map_err(|err| err.to_string()),
+            LevelSource::Generated { algorithm, seed, width, height } => {
+                Ok(generate(*algorithm, *width, *height, *seed))
+            }
+        }
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut level_paths = Vec::new();
+    let mut check = false;
+    let mut benchmark = None;
+    let mut threads = default_threads();
+    let mut render_output = None;
+    let mut position = None;
+    let mut angle = None;
+    let mut resolution = (1280, 720);
+    let mut reference = None;
+    let mut algorithm = None;
+    let mut seed = None;
+    let mut size = (21, 21);
+    let mut save_path = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--generar" => {
+                let name = next_value(&mut iter, arg)?;
+                algorithm = Some(
+                    Algorithm::from_name(name).ok_or_else(|| format!("algoritmo desconocido `{}`", name))?,
+                );
+            }
+            "--semilla" => {
+                let value = next_value(&mut iter, arg)?;
+                seed = Some(value.parse().map_err(|_| format!("semilla inválida `{}`", value))?);
+            }
+            "--tamano" => {
+                let value = next_value(&mut iter, arg)?;
+                size = parse_size(value).ok_or_else(|| format!("tamaño inválido `{}` (usa ANCHOxALTO)", value))?;
+            }
+            "--guardar" => save_path = Some(next_value(&mut iter, arg)?.to_string()),
+            "--comprobar" => check = true,
+            "--benchmark" => {
+                // El número de fotogramas es opcional
+                let frames = iter.clone().next().and_then(|value| value.parse().ok());
+                if frames.is_some() {
+                    iter.next();
+                }
+                benchmark = Some(frames.unwrap_or(120));
+            }
+            "--hilos" => {
+                let value = next_value(&mut iter, arg)?;
+                threads = value
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("número de hilos inválido `{}`", value))?;
+            }
+            "--render" => render_output = Some(next_value(&mut iter, arg)?.to_string()),
+            "--posicion" => {
+                let value = next_value(&mut iter, arg)?;
+                position = Some(parse_position(value).ok_or_else(|| format!("posición inválida `{}` (usa FILA,COL)", value))?);
+            }
+            "--angulo" => {
+                let value = next_value(&mut iter, arg)?;
+                angle = Some(value.parse::<f32>().map_err(|_| format!("ángulo inválido `{}`", value))?.to_radians());
+            }
+            "--resolucion" => {
+                let value = next_value(&mut iter, arg)?;
+                resolution = parse_size(value)
+                    .filter(|&(width, height)| width > 0 && height > 0)
+                    .ok_or_else(|| format!("resolución inválida `{}` (usa ANCHOxALTO)", value))?;
+                let pixels = resolution.0.checked_mul(resolution.1);
+                if pixels.is_none_or(|pixels| pixels > MAX_RESOLUTION_PIXELS) {
+                    return Err(format!("resolución demasiado grande `{}` (como mucho {} píxeles)", value, MAX_RESOLUTION_PIXELS));
+                }
+            }
+            "--comparar" => reference = Some(next_value(&mut iter, arg)?.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("opción desconocida `{}`", arg)),
+            _ => level_paths.push(arg.clone()),
+        }
+    }
+
+    let mut levels: Vec<LevelSource> = level_paths.into_iter().map(LevelSource::File).collect();
+    if let Some(algorithm) = algorithm {
+        levels.push(LevelSource::Generated {
+            algorithm,
+            seed: seed.unwrap_or_else(time_seed),
+            width: size.0,
+            height: size.1,
+        });
+    }
+    let explicit_level = !levels.is_empty();
+    if levels.is_empty() {
+        levels.push(LevelSource::File(DEFAULT_LEVEL.to_string()));
+    }
+
+    if check {
+        return Ok(Command::Check(levels));
+    }
+    if levels.len() > 1 {
+        return Err("solo se puede usar un nivel a la vez".to_string());
+    }
+    let level = levels.remove(0);
+    if let Some(output) = render_output {
+        return Ok(Command::Render(RenderOptions {
+            level,
+            output,
+            position,
+            angle,
+            size: resolution,
+            reference,
+            threads,
+        }));
+    }
+    if let Some(frames) = benchmark {
+        return Ok(Command::Benchmark { level, frames, threads });
+    }
+    Ok(Command::Play { level: explicit_level.then_some(level), save_path, threads })
+}
+
+// Herramienta de comprobación: informa de todos los problemas de cada nivel.
+// Devuelve el código de salida del proceso.
+pub fn run_check(levels: &[LevelSource]) -> i32 {
+    let mut failed = 0;
+    for level in levels {
+        let (label, issues) = match level {
+            LevelSource::File(path) => match Maze::load(path) {
+                Ok(_) => (path.clone(), Vec::new()),
+                Err(MazeError::Invalid(_, issues)) => (path.clone(), issues),
+                Err(err) => {
+                    println!("ERROR {}", err);
+                    failed += 1;
+                    continue;
+                }
+            },
+            LevelSource::Generated { .. } => {
+                let maze = match level.load() {
+                    Ok(maze) => maze,
+                    Err(err) => {
+                        println!("ERROR {}", err);
+                        failed += 1;
+                        continue;
+                    }
+                };
+                (maze.name.clone(), validate(&maze))
+            }
+        };
+
+        if issues.is_empty() {
+            println!("OK    {}", label);
+        } else {
+            failed += 1;
+            println!("ERROR {}: {} problema(s)", label, issues.len());
+            for issue in &issues {
+                println!("  {}", issue);
+            }
+        }
+    }
+    if failed > 0 { 1 } else { 0 }
+}
+
+// Modo benchmark: renderiza el nivel sin ventana con un hilo y con `threads` hilos,
+// comprueba que ambos resultados son idénticos y muestra la aceleración.
+pub fn run_benchmark(level: &LevelSource, frames: usize, threads: usize, width: i32, height: i32, block_size: i32, fov: f32) -> i32 {
+    let maze = match level.load() {
+        Ok(maze) => maze,
+        Err(err) => {
+            eprintln!("Error cargando el nivel {}", err);
+            return 1;
+        }
+    };
+    let textures = TextureSet::load(TEXTURE_DIR);
+    let sprites = Sprite::from_decorations(&maze, block_size);
+    let frames = frames.max(1);
+
+    // Una vuelta completa sobre la celda de inicio
+    let render = |thread_count: usize| {
+        let mut framebuffer = Framebuffer::new(width as u32, height as u32);
+        let mut player = Player::new(
+            (maze.start.1 as f32 + 0.5) * block_size as f32,
+            (maze.start.0 as f32 + 0.5) * block_size as f32,
+        );
+        let mut checksum = 0u64;
+        let started = Instant::now();
+        for frame in 0..frames {
+            player.angle = maze.start_angle + frame as f32 / frames as f32 * std::f32::consts::TAU;
+            let time = frame as f64 / 60.0;
+            let view = SceneView {
+                fov,
+                block_size,
+                time,
+                invulnerability_time: 0.0,
+                screen_width: width,
+                screen_height: height,
+                threads: thread_count,
+            };
+            render_scene(&mut framebuffer, &maze, &textures, &sprites, &player, view);
+            for pixel in &framebuffer.pixels {
+                checksum = checksum.wrapping_mul(31).wrapping_add(u32::from_le_bytes([pixel.r, pixel.g, pixel.b, pixel.a]) as u64);
+            }
+        }
+        (started.elapsed().as_secs_f64() * 1000.0 / frames as f64, checksum)
+    };
+
+    println!("{} - {} fotogramas a {}x{}", maze.name, frames, width, height);
+    let (single_ms, single_sum) = render(1);
+    println!("  1 hilo:    {:7.2} ms/fotograma ({:6.1} FPS)", single_ms, 1000.0 / single_ms);
+    let (multi_ms, multi_sum) = render(threads);
+    println!("  {} hilos: {:7.2} ms/fotograma ({:6.1} FPS)", threads, multi_ms, 1000.0 / multi_ms);
+    println!("  Aceleración: x{:.2}", single_ms / multi_ms);
+
+    if single_sum != multi_sum {
+        println!("  ERROR: el resultado multihilo no coincide con el de un hilo");
+        return 1;
+    }
+    println!("  Resultado idéntico en ambos modos");
+    0
+}
+
+// Renderiza un único fotograma sin ventana y lo guarda como imagen. Con `reference`
+// compara el resultado con una imagen de referencia (prueba de regresión del renderer).
+pub fn run_render(options: &RenderOptions, block_size: i32, fov: f32) -> i32 {
+    let maze = match options.level.load() {
+        Ok(maze) => maze,
+        Err(err) => {
+            eprintln!("Error cargando el nivel {}", err);
+            return 1;
+        }
+    };
+    let framebuffer = render_frame(&maze, options, block_size, fov);
+
+    let output = &options.output;
+    if let Err(err) = framebuffer.save(output) {
+        eprintln!("Error guardando la imagen {}", err);
+        return 1;
+    }
+    println!("{} -> {}", maze.name, output);
+
+    let Some(reference) = &options.reference else {
+        return 0;
+    };
+    let expected = match Framebuffer::load_ppm(reference) {
+        Ok(expected) => expected,
+        Err(err) => {
+            eprintln!("Error leyendo la referencia {}", err);
+            return 1;
+        }
+    };
+    match framebuffer.count_differences(&expected, REFERENCE_TOLERANCE) {
+        Some(0) => {
+            println!("OK    coincide con {}", reference);
+            0
+        }
+        Some(count) => {
+            println!("ERROR {} píxeles distintos de {}", count, reference);
+            1
+        }
+        None => {
+            println!(
+                "ERROR {} mide {}x{}, el render {}x{}",
+                reference, expected.width, expected.height, framebuffer.width, framebuffer.height
+            );
+            1
+        }
+    }
+}
+
+// El fotograma de `--render`, sin guardarlo
+fn render_frame(maze: &Maze, options: &RenderOptions, block_size: i32, fov: f32) -> Framebuffer {
+    let textures = TextureSet::load(TEXTURE_DIR);
+    // La escena tal como empieza el nivel: decoraciones, llaves, objetos y enemigos en su sitio
+    let mut sprites = Sprite::from_decorations(maze, block_size);
+    sprites.extend(Sprite::from_keys(&maze.keys, block_size));
+    sprites.extend(Sprite::from_pickups(&maze.pickups, block_size));
+    sprites.extend(Sprite::from_enemies(&Enemy::from_maze(maze, block_size)));
+
+    let (row, col) = options.position.unwrap_or((maze.start.0 as f32 + 0.5, maze.start.1 as f32 + 0.5));
+    let mut player = Player::new(col * block_size as f32, row * block_size as f32);
+    player.angle = options.angle.unwrap_or(maze.start_angle);
+
+    // Tiempo 0: las animaciones quedan en su primer cuadro y el resultado es reproducible
+    let (width, height) = (options.size.0 as i32, options.size.1 as i32);
+    let mut framebuffer = Framebuffer::new(width as u32, height as u32);
+    let view = SceneView {
+        fov,
+        block_size,
+        time: 0.0,
+        invulnerability_time: 0.0,
+        screen_width: width,
+        screen_height: height,
+        threads: options.threads,
+    };
+    render_scene(&mut framebuffer, maze, &textures, &sprites, &player, view);
+    framebuffer
+}
+
+fn next_value<'a>(iter: &mut std::slice::Iter<'a, String>, option: &str) -> Result<&'a str, String> {
+    iter.next()
+        .map(|value| value.as_str())
+        .ok_or_else(|| format!("falta el valor de `{}`", option))
+}
+
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+fn parse_position(value: &str) -> Option<(f32, f32)> {
+    let (row, col) = value.split_once(',')?;
+    Some((row.trim().parse().ok()?, col.trim().parse().ok()?))
+}
+
+// Semilla por defecto cuando no se indica ninguna; se muestra para poder compartirla
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+
+    // Imagen de referencia del laberinto clásico desde su inicio. Si un cambio del renderer la
+    // altera a propósito, se regenera con `ACTUALIZAR_REFERENCIAS=1 cargo test`
+    const GOLDEN_LEVEL: &str = "assets/levels/clasico.txt";
+    const GOLDEN_IMAGE: &str = "assets/referencias/clasico_160x90.ppm";
+
+    #[test]
+    fn golden_render_matches_reference() {
+        let options = RenderOptions {
+            level: LevelSource::File(GOLDEN_LEVEL.to_string()),
+            output: String::new(),
+            position: None,
+            angle: None,
+            size: (160, 90),
+            reference: Some(GOLDEN_IMAGE.to_string()),
+            threads: 2,
+        };
+        let maze = options.level.load().unwrap();
+        let framebuffer = render_frame(&maze, &options, 64, Settings::default().fov());
+        if std::env::var_os("ACTUALIZAR_REFERENCIAS").is_some() {
+            framebuffer.save(GOLDEN_IMAGE).unwrap();
+        }
+        let expected = Framebuffer::load_ppm(GOLDEN_IMAGE).unwrap();
+        assert_eq!(framebuffer.count_differences(&expected, REFERENCE_TOLERANCE), Some(0));
+    }
+
+    #[test]
+    fn rejects_resolutions_too_large_to_allocate() {
+        let args = |resolution: &str| ["--render", "x.ppm", "--resolucion", resolution].map(String::from);
+        assert!(parse_args(&args("320x200")).is_ok());
+        assert!(parse_args(&args("0x200")).is_err());
+        assert!(parse_args(&args("100000x100000")).is_err());
+        assert!(parse_args(&args("18446744073709551615x2")).is_err());
+    }
+}
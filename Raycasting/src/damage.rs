@@ -0,0 +1,89 @@
+use crate::maze::Maze;
+use crate::settings::Settings;
+
+// Cómo hacen daño los choques con las paredes. Se elige en los ajustes (`danio`); en
+// `auto` manda la cabecera del nivel y, si no la tiene, la dificultad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamagePolicy {
+    // Cada choque quita una vida entera
+    Lives,
+    // Barra de salud: el daño depende de la velocidad con la que se choca
+    HealthBar,
+    // Solo hacen daño las paredes peligrosas del nivel
+    HazardOnly,
+    // Sin daño
+    Zen,
+}
+
+impl DamagePolicy {
+    pub const ALL: [DamagePolicy; 4] = [DamagePolicy::Lives, DamagePolicy::HealthBar, DamagePolicy::HazardOnly, DamagePolicy::Zen];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DamagePolicy::Lives => "vidas",
+            DamagePolicy::HealthBar => "salud",
+            DamagePolicy::HazardOnly => "peligro",
+            DamagePolicy::Zen => "zen",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DamagePolicy> {
+        DamagePolicy::ALL.into_iter().find(|policy| policy.name() == name)
+    }
+}
+
+// Paredes peligrosas si el nivel no declara `peligro`
+pub const DEFAULT_HAZARDS: &[char] = &['D'];
+
+// Con `HealthBar`, los roces por debajo de esta fracción del avance no hacen daño
+const MIN_IMPACT: f32 = 0.15;
+
+// Reglas de daño de una partida, resueltas al empezarla
+#[derive(Debug, Clone)]
+pub struct Damage {
+    pub policy: DamagePolicy,
+    pub hazards: Vec<char>,
+    // Salud al empezar; cada corazón del HUD vale 1
+    pub max_health: f32,
+    // Segundos de invulnerabilidad tras recibir daño
+    pub invulnerability: f64,
+}
+
+impl Damage {
+    pub fn resolve(settings: &Settings, maze: &Maze) -> Damage {
+        let difficulty = settings.difficulty;
+        let policy = settings.damage.or(maze.damage).unwrap_or(difficulty.damage_policy());
+        let hazards = if maze.hazards.is_empty() { DEFAULT_HAZARDS.to_vec() } else { maze.hazards.clone() };
+        Damage {
+            policy,
+            hazards,
+            max_health: difficulty.lives() as f32,
+            invulnerability: difficulty.invulnerability(),
+        }
+    }
+
+    // Letras de las paredes peligrosas, para mostrarlas ("C, D")
+    pub fn hazard_names(&self) -> String {
+        self.hazards.iter().map(|wall| wall.to_string()).collect::<Vec<_>>().join(", ")
+    }
+
+    // Daño de un choque contra `wall`; `impact` es la fracción del avance que frenó la pared
+    // (1 de frente, casi 0 al rozarla)
+    pub fn amount(&self, wall: char, impact: f32) -> f32 {
+        match self.policy {
+            DamagePolicy::Lives => 1.0,
+            DamagePolicy::HealthBar if impact < MIN_IMPACT => 0.0,
+            DamagePolicy::HealthBar => impact.min(1.0),
+            DamagePolicy::HazardOnly if self.hazards.contains(&wall) => 1.0,
+            DamagePolicy::HazardOnly | DamagePolicy::Zen => 0.0,
+        }
+    }
+
+    // Daño al tocar a un enemigo: siempre un corazón entero, salvo en zen
+    pub fn enemy_amount(&self) -> f32 {
+        match self.policy {
+            DamagePolicy::Zen => 0.0,
+            _ => 1.0,
+        }
+    }
+}
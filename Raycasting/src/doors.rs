@@ -0,0 +1,138 @@
+use raylib::prelude::Color;
+use crate::player::Inventory;
+
+// Puertas de la cuadrícula: `P` se abre con la acción de interactuar y `1`, `2` y `3` están
+// cerradas con llave y solo se abren con la llave de su color
+pub const DOOR_CHARS: &[char] = &['P', '1', '2', '3'];
+
+// Segundos que tarda una puerta en abrirse o cerrarse del todo
+const DOOR_TIME: f32 = 0.6;
+
+// Grosor de la puerta en fracción de bloque; está empotrada en el centro de su celda
+pub const DOOR_THICKNESS: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeyColor {
+    Red,
+    Green,
+    Blue,
+}
+
+impl KeyColor {
+    pub const ALL: [KeyColor; 3] = [KeyColor::Red, KeyColor::Green, KeyColor::Blue];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyColor::Red => "roja",
+            KeyColor::Green => "verde",
+            KeyColor::Blue => "azul",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<KeyColor> {
+        KeyColor::ALL.into_iter().find(|color| color.name() == name)
+    }
+
+    // Carácter de la puerta que abre
+    pub fn door_char(self) -> char {
+        match self {
+            KeyColor::Red => '1',
+            KeyColor::Green => '2',
+            KeyColor::Blue => '3',
+        }
+    }
+
+    pub fn from_door_char(cell: char) -> Option<KeyColor> {
+        KeyColor::ALL.into_iter().find(|color| color.door_char() == cell)
+    }
+
+    // Tipo de sprite con el que se dibuja en el suelo
+    pub fn sprite(self) -> &'static str {
+        match self {
+            KeyColor::Red => "llave_roja",
+            KeyColor::Green => "llave_verde",
+            KeyColor::Blue => "llave_azul",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            KeyColor::Red => Color::new(230, 50, 50, 255),
+            KeyColor::Green => Color::new(50, 210, 80, 255),
+            KeyColor::Blue => Color::new(60, 120, 240, 255),
+        }
+    }
+}
+
+pub fn is_door_char(cell: char) -> bool {
+    DOOR_CHARS.contains(&cell)
+}
+
+// Llave en el suelo del nivel (`llave = fila columna color`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Key {
+    pub row: usize,
+    pub col: usize,
+    pub color: KeyColor,
+}
+
+#[derive(Debug, Clone)]
+pub struct Door {
+    pub row: usize,
+    pub col: usize,
+    pub lock: Option<KeyColor>,
+    // En vertical (de norte a sur, entre muros arriba y abajo) o en horizontal
+    pub vertical: bool,
+    // Cuánto se ha deslizado, de 0 (cerrada) a 1 (abierta)
+    pub open: f32,
+    // Hacia dónde se está moviendo
+    pub opening: bool,
+}
+
+impl Door {
+    // Mientras no esté abierta del todo la puerta bloquea el paso
+    pub fn is_blocking(&self) -> bool {
+        self.open < 1.0
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let step = dt / DOOR_TIME;
+        self.open = if self.opening { (self.open + step).min(1.0) } else { (self.open - step).max(0.0) };
+    }
+
+    // Abre la puerta si está cerrada o cerrándose y la cierra si está abierta o abriéndose.
+    // `occupied` indica que el jugador está dentro de su celda
+    pub fn interact(&mut self, inventory: &Inventory, occupied: bool) -> DoorAction {
+        if self.opening {
+            if occupied {
+                return DoorAction::Blocked;
+            }
+            self.opening = false;
+            return DoorAction::Closed;
+        }
+        if let Some(lock) = self.lock
+            && !inventory.has_key(lock)
+        {
+            return DoorAction::Locked(lock);
+        }
+        self.opening = true;
+        DoorAction::Opened
+    }
+
+    // Abre o cierra la puerta al instante, como al cargar una partida
+    pub fn set_open(&mut self, open: bool) {
+        self.opening = open;
+        self.open = if open { 1.0 } else { 0.0 };
+    }
+}
+
+// Lo que pasa al interactuar con una puerta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorAction {
+    Opened,
+    Closed,
+    // Cerrada con una llave que el jugador no tiene
+    Locked(KeyColor),
+    // No se cierra con el jugador dentro
+    Blocked,
+}
@@ -0,0 +1,189 @@
+use crate::maze::Maze;
+use crate::pathfinding::{cell_at, find_path_with, Algorithm, Cell, Doors};
+use crate::physics::move_circle;
+use crate::player::Player;
+use crate::renderer::cast_ray;
+use crate::teleporters::teleport;
+
+// Enemigos que recorren una ruta de celdas (`enemigo = 3 5, 3 10, 8 10` en el nivel) y
+// persiguen al jugador mientras lo ven. Van de celda en celda por el camino de A* y chocan
+// con los muros y las puertas cerradas igual que el jugador. No abren puertas: el camino
+// solo pasa por las que están abiertas del todo, y si no hay otro siguen con la ruta.
+
+// Tipo de sprite con el que se dibujan
+pub const ENEMY_SPRITE: &str = "enemigo";
+
+// Radio del círculo con el que chocan y tocan al jugador
+pub const ENEMY_RADIUS: f32 = 14.0;
+
+// Píxeles por segundo; el jugador va a 150
+const PATROL_SPEED: f32 = 60.0;
+const CHASE_SPEED: f32 = 105.0;
+
+// Distancia máxima a la que ven al jugador, en bloques
+const SIGHT_RANGE: f32 = 8.0;
+
+// A menos de esto del centro de una celda se da por alcanzada
+const ARRIVE_DISTANCE: f32 = 2.0;
+
+#[derive(Debug, Clone)]
+pub struct Enemy {
+    pub x: f32,
+    pub y: f32,
+    // Celdas de la ruta; empiezan en la primera y la recorren en bucle
+    pub patrol: Vec<Cell>,
+    // Punto de la ruta hacia el que van
+    pub waypoint: usize,
+    // Última celda en la que vieron al jugador; al llegar sin volver a verlo siguen la ruta
+    pub last_seen: Option<Cell>,
+    // Celdas que quedan del camino hacia `goal`
+    path: Vec<Cell>,
+    goal: Option<Cell>,
+    // Celda a la que llegaron por un teletransporte, como `Session::teleport_lock`
+    teleport_lock: Option<Cell>,
+}
+
+impl Enemy {
+    pub fn new(patrol: Vec<Cell>, block_size: i32) -> Enemy {
+        let (row, col) = patrol.first().copied().unwrap_or((1, 1));
+        Enemy {
+            x: (col as f32 + 0.5) * block_size as f32,
+            y: (row as f32 + 0.5) * block_size as f32,
+            waypoint: 1 % patrol.len().max(1),
+            patrol,
+            last_seen: None,
+            path: Vec::new(),
+            goal: None,
+            teleport_lock: None,
+        }
+    }
+
+    // Los enemigos del nivel en su posición inicial
+    pub fn from_maze(maze: &Maze, block_size: i32) -> Vec<Enemy> {
+        maze.enemies.iter().map(|patrol| Enemy::new(patrol.clone(), block_size)).collect()
+    }
+
+    pub fn is_chasing(&self) -> bool {
+        self.last_seen.is_some()
+    }
+
+    // Ve el punto si está a su alcance y el rayo hacia él no choca antes con un muro o una puerta
+    // ni se desvía por un portal
+    pub fn sees(&self, maze: &Maze, x: f32, y: f32, block_size: i32) -> bool {
+        let distance = (x - self.x).hypot(y - self.y);
+        if distance > SIGHT_RANGE * block_size as f32 {
+            return false;
+        }
+        let angle = (y - self.y).atan2(x - self.x);
+        cast_ray(maze, self.x, self.y, angle, block_size) >= distance
+    }
+
+    pub fn touches(&self, player: &Player) -> bool {
+        (player.x - self.x).hypot(player.y - self.y) < player.radius + ENEMY_RADIUS
+    }
+
+    pub fn update(&mut self, maze: &Maze, player: &Player, dt: f32, block_size: i32) {
+        let visible = self.sees(maze, player.x, player.y, block_size);
+        if visible {
+            self.last_seen = Some(cell_at(player.x, player.y, block_size));
+        }
+        let speed = if self.is_chasing() { CHASE_SPEED } else { PATROL_SPEED };
+
+        // Con el jugador a la vista van derechos hacia él; si no, por el camino hasta su objetivo
+        let (target_x, target_y) = if visible {
+            (player.x, player.y)
+        } else {
+            let Some(goal) = self.last_seen.or_else(|| self.patrol.get(self.waypoint).copied()) else {
+                return;
+            };
+            if self.goal != Some(goal) {
+                self.goal = Some(goal);
+                let here = cell_at(self.x, self.y, block_size);
+                self.path = find_path_with(maze, here, goal, Algorithm::AStar, Doors::OpenOnly).map_or_else(Vec::new, |path| path.cells);
+            }
+            let center = |(row, col): Cell| ((col as f32 + 0.5) * block_size as f32, (row as f32 + 0.5) * block_size as f32);
+            while let Some(&cell) = self.path.first() {
+                let (x, y) = center(cell);
+                if (x - self.x).hypot(y - self.y) > ARRIVE_DISTANCE {
+                    break;
+                }
+                self.path.remove(0);
+            }
+            match self.path.first() {
+                Some(&cell) => center(cell),
+                // Objetivo alcanzado, o inalcanzable: se sigue con la ruta
+                None => {
+                    self.goal = None;
+                    if self.last_seen.take().is_none() {
+                        self.waypoint = (self.waypoint + 1) % self.patrol.len();
+                    }
+                    return;
+                }
+            }
+        };
+
+        let (dx, dy) = (target_x - self.x, target_y - self.y);
+        let distance = dx.hypot(dy);
+        if distance <= f32::EPSILON {
+            return;
+        }
+        let step = (speed * dt).min(distance);
+        let movement = move_circle(maze, self.x, self.y, dx / distance * step, dy / distance * step, ENEMY_RADIUS, block_size);
+        self.x = movement.x;
+        self.y = movement.y;
+        // También usan los teletransportes; al otro lado el camino se vuelve a calcular
+        if let Some((x, y, _)) = teleport(maze, self.x, self.y, 0.0, &mut self.teleport_lock, block_size) {
+            self.x = x;
+            self.y = y;
+            self.goal = None;
+            return;
+        }
+        // Al perseguir en línea recta el camino guardado deja de valer
+        if visible {
+            self.goal = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_SIZE: i32 = 64;
+
+    // La única forma de pasar de la sala de abajo a la de arriba es la puerta
+    const LEVEL: &str = "\
+inicio = 1 4
+salida = 3 5
+enemigo = 3 1, 1 1
+---
+######
+#    #
+#P####
+#    E
+######
+";
+
+    #[test]
+    fn enemies_wait_behind_closed_doors() {
+        let mut maze = Maze::parse(LEVEL).unwrap();
+        let player = Player::new(4.5 * BLOCK_SIZE as f32, 1.5 * BLOCK_SIZE as f32);
+        let mut enemy = Enemy::from_maze(&maze, BLOCK_SIZE).remove(0);
+        let start = (enemy.x, enemy.y);
+
+        // Con la puerta cerrada no hay camino: no se queda empujándola
+        for _ in 0..120 {
+            enemy.update(&maze, &player, 1.0 / 60.0, BLOCK_SIZE);
+        }
+        assert_eq!((enemy.x, enemy.y), start);
+
+        // Abierta, sigue su ruta hasta el otro lado
+        maze.door_mut(2, 1).unwrap().set_open(true);
+        let mut reached = false;
+        for _ in 0..600 {
+            enemy.update(&maze, &player, 1.0 / 60.0, BLOCK_SIZE);
+            reached |= cell_at(enemy.x, enemy.y, BLOCK_SIZE) == (1, 1);
+        }
+        assert!(reached, "el enemigo no cruzó la puerta abierta");
+    }
+}
@@ -1,4 +1,6 @@
 use raylib::prelude::*;
+use std::fs;
+use std::path::Path;
 
 pub struct Framebuffer {
     pub pixels: Vec<Color>,
@@ -7,12 +9,15 @@ pub struct Framebuffer {
     pub current_color: Color,
     pub background_color: Color,
     texture: Option<Texture2D>,
+    // Hay píxeles que cambiaron desde la última subida a la GPU
+    dirty: bool,
 }
 
 impl Framebuffer {
     // Crear el frame buffer
     pub fn new(width: u32, height: u32) -> Self {
-        let total_pixels = (width * height) as usize;
+        // En `usize` para que el producto no desborde; quien lea el tamaño de fuera debe acotarlo
+        let total_pixels = width as usize * height as usize;
         Self {
             pixels: vec![Color::BLACK; total_pixels],
             width,
@@ -20,14 +25,23 @@ impl Framebuffer {
             current_color: Color::WHITE,
             background_color: Color::BLACK,
             texture: None,
+            dirty: true,
         }
     }
 
+    // Marca el framebuffer como modificado; hace falta al escribir directamente en `pixels`.
+    // La escena 3D repinta todas las filas en cada fotograma, así que no se lleva la cuenta de
+    // cuáles cambiaron: se sube la textura entera o nada
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
     // Limpio el framebuffer
     pub fn clear(&mut self) {
         for pixel in &mut self.pixels {
             *pixel = self.background_color;
         }
+        self.mark_dirty();
     }
 
     pub fn set_background_color(&mut self, color: Color) {
@@ -42,6 +56,7 @@ impl Framebuffer {
         if x < self.width && y < self.height {
             let index = (y * self.width + x) as usize;
             self.pixels[index] = self.current_color;
+            self.mark_dirty();
         }
     }
 
@@ -49,6 +64,7 @@ impl Framebuffer {
         if x < self.width && y < self.height {
             let index = (y * self.width + x) as usize;
             self.pixels[index] = color;
+            self.mark_dirty();
         }
     }
 
@@ -106,30 +122,136 @@ impl Framebuffer {
         }
     }
 
-    pub fn swap_buffers(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
-        // Crear una nueva imagen de forma más eficiente
-        let mut image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
-        
-        // Llenar pixel por pixel
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let pixel = self.get_pixel(x, y);
-                image.draw_pixel(x as i32, y as i32, pixel);
+    // Guarda el framebuffer como imagen; el formato sale de la extensión (.png o .ppm).
+    // No necesita ventana: todo ocurre en CPU.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("ppm") => self.save_ppm(path),
+            Some("png") => self.save_png(path),
+            _ => Err(format!("formato de imagen no soportado en `{}` (usa .png o .ppm)", path)),
+        }
+    }
+
+    // PPM binario (P6): cabecera de texto y después RGB sin comprimir
+    pub fn save_ppm(&self, path: &str) -> Result<(), String> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        data.reserve(self.pixels.len() * 3);
+        for pixel in &self.pixels {
+            data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+        fs::write(path, data).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // PNG mediante una `Image` de raylib, que se codifica en CPU
+    pub fn save_png(&self, path: &str) -> Result<(), String> {
+        let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+        let bytes = pixel_bytes(&self.pixels);
+        // SAFETY: `gen_image_color` reserva ancho x alto píxeles RGBA de 8 bits, igual que `bytes`
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), image.data() as *mut u8, bytes.len());
+        }
+        // `export_image` no dice si ha fallado: se borra antes lo que hubiera para que no cuente
+        // como escrita una imagen de una ejecución anterior
+        if let Err(err) = fs::remove_file(path)
+            && err.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(format!("{}: {}", path, err));
+        }
+        image.export_image(path);
+        if Path::new(path).exists() {
+            Ok(())
+        } else {
+            Err(format!("{}: no se pudo escribir la imagen", path))
+        }
+    }
+
+    // Lee un PPM P6 como los que escribe `save_ppm` (por ejemplo, imágenes de referencia)
+    pub fn load_ppm(path: &str) -> Result<Framebuffer, String> {
+        let data = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+        let invalid = || format!("{}: PPM inválido (se espera P6 de 8 bits)", path);
+
+        // Cuatro campos de cabecera separados por espacios; se admiten comentarios `#`
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        while fields.len() < 4 {
+            while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+                if data[pos] == b'#' {
+                    while pos < data.len() && data[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid());
             }
+            fields.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
         }
+        // Un único espacio separa la cabecera de los datos
+        pos += 1;
 
-        // Solo liberar la textura anterior si existe
-        if let Some(_old_texture) = self.texture.take() {
-            // La textura se libera automáticamente al salir de scope
+        let width: u32 = fields[1].parse().map_err(|_| invalid())?;
+        let height: u32 = fields[2].parse().map_err(|_| invalid())?;
+        // Los tamaños vienen del archivo: se comprueba que no desborden antes de fiarse de ellos
+        let byte_count = (width as usize).checked_mul(height as usize).and_then(|count| count.checked_mul(3));
+        let Some(byte_count) = byte_count else {
+            return Err(invalid());
+        };
+        if fields[0] != "P6" || fields[3] != "255" || data.len().saturating_sub(pos) < byte_count {
+            return Err(invalid());
         }
 
-        // Crear nueva textura desde la imagen
-        match rl.load_texture_from_image(thread, &image) {
-            Ok(texture) => self.texture = Some(texture),
-            Err(_) => {
-                // Fallback: mantener la textura anterior si falla la carga
-                eprintln!("Error cargando textura del framebuffer");
+        let mut framebuffer = Framebuffer::new(width, height);
+        for (pixel, rgb) in framebuffer.pixels.iter_mut().zip(data[pos..].chunks_exact(3)) {
+            *pixel = Color::new(rgb[0], rgb[1], rgb[2], 255);
+        }
+        Ok(framebuffer)
+    }
+
+    // Número de píxeles cuya diferencia en algún canal supera `tolerance`;
+    // `None` si los tamaños no coinciden
+    pub fn count_differences(&self, other: &Framebuffer, tolerance: u8) -> Option<usize> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+        let differs = |a: &Color, b: &Color| {
+            a.r.abs_diff(b.r) > tolerance || a.g.abs_diff(b.g) > tolerance || a.b.abs_diff(b.b) > tolerance
+        };
+        Some(self.pixels.iter().zip(&other.pixels).filter(|(a, b)| differs(a, b)).count())
+    }
+
+    // Sube los píxeles a la GPU si cambiaron. La textura se crea una única vez y
+    // después se actualiza en su sitio.
+    pub fn swap_buffers(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        if self.texture.is_none() {
+            let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+            match rl.load_texture_from_image(thread, &image) {
+                Ok(texture) => self.texture = Some(texture),
+                Err(_) => {
+                    eprintln!("Error cargando textura del framebuffer");
+                    return;
+                }
             }
+            self.dirty = true;
+        }
+
+        if !std::mem::take(&mut self.dirty) {
+            return;
+        }
+        let Some(texture) = self.texture.as_mut() else {
+            return;
+        };
+        if let Err(err) = texture.update_texture(pixel_bytes(&self.pixels)) {
+            eprintln!("Error actualizando textura del framebuffer: {}", err);
         }
     }
 
@@ -143,4 +265,76 @@ impl Framebuffer {
             );
         }
     }
-}
\ No newline at end of file
+}
+
+// Vista de los píxeles como bytes RGBA, el formato de la textura del framebuffer
+fn pixel_bytes(pixels: &[Color]) -> &[u8] {
+    // SAFETY: `Color` es `repr(C)` con cuatro campos `u8` (r, g, b, a), sin relleno
+    unsafe { std::slice::from_raw_parts(pixels.as_ptr() as *const u8, pixels.len() * 4) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Archivo temporal propio de cada prueba, para que puedan ir en paralelo
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("raycasting_{}_{}", std::process::id(), name)).to_string_lossy().into_owned()
+    }
+
+    fn load_bytes(name: &str, data: &[u8]) -> Result<Framebuffer, String> {
+        let path = temp_path(name);
+        fs::write(&path, data).unwrap();
+        let result = Framebuffer::load_ppm(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn ppm_round_trips() {
+        let mut framebuffer = Framebuffer::new(7, 5);
+        for (index, pixel) in framebuffer.pixels.iter_mut().enumerate() {
+            *pixel = Color::new((index * 37) as u8, (index * 11) as u8, 255 - index as u8, 255);
+        }
+        let path = temp_path("ida_y_vuelta.ppm");
+        framebuffer.save(&path).unwrap();
+        let loaded = Framebuffer::load_ppm(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((loaded.width, loaded.height), (7, 5));
+        assert_eq!(loaded.pixels, framebuffer.pixels);
+        assert_eq!(loaded.count_differences(&framebuffer, 0), Some(0));
+    }
+
+    #[test]
+    fn reads_headers_with_comments() {
+        let framebuffer = load_bytes("comentarios.ppm", b"P6\n# hecho a mano\n2 1\n255\n\x01\x02\x03\x04\x05\x06").unwrap();
+        assert_eq!(framebuffer.pixels, vec![Color::new(1, 2, 3, 255), Color::new(4, 5, 6, 255)]);
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_files() {
+        assert!(load_bytes("corto.ppm", b"P6\n2 2\n255\n\x01\x02\x03").is_err());
+        assert!(load_bytes("sin_datos.ppm", b"P6\n2 2\n").is_err());
+        assert!(load_bytes("texto.ppm", b"P3\n1 1\n255\n1 2 3\n").is_err());
+        assert!(load_bytes("16_bits.ppm", b"P6\n1 1\n65535\n\x01\x02\x03\x04\x05\x06").is_err());
+    }
+
+    #[test]
+    fn rejects_sizes_that_overflow() {
+        // Ancho por alto cabe en 64 bits, pero no al multiplicar por los tres canales
+        assert!(load_bytes("enorme.ppm", b"P6\n4294967295 4294967295 255\n\x01\x02\x03").is_err());
+        assert!(load_bytes("grande.ppm", b"P6 70000 70000 255\n\x01\x02\x03").is_err());
+    }
+
+    #[test]
+    fn counts_differences_above_the_tolerance() {
+        let a = Framebuffer::new(3, 1);
+        let mut b = Framebuffer::new(3, 1);
+        b.pixels[0] = Color::new(2, 0, 0, 255);
+        b.pixels[2] = Color::new(0, 0, 9, 255);
+        assert_eq!(a.count_differences(&b, 0), Some(2));
+        assert_eq!(a.count_differences(&b, 2), Some(1));
+        assert_eq!(a.count_differences(&Framebuffer::new(1, 3), 0), None);
+    }
+}
@@ -0,0 +1,1079 @@
+use raylib::prelude::*;
+
+use crate::audio::{Audio, BUMP_SOUND, PICKUP_SOUND};
+use crate::campaign::{Campaign, PROGRESS_FILE};
+use crate::cli::LevelSource;
+use crate::damage::{Damage, DamagePolicy};
+use crate::doors::{is_door_char, DoorAction, Key};
+use crate::enemies::Enemy;
+use crate::framebuffer::Framebuffer;
+use crate::maze::Maze;
+use crate::pathfinding::{find_path, cell_at, par_time, Algorithm, Cell, Path};
+use crate::pickups::{in_reach, Pickup, PickupKind, INVULNERABILITY_TIME, SPEED_BOOST, SPEED_BOOST_TIME};
+use crate::player::{Inventory, Player};
+use crate::renderer::{render_scene, SceneView, Sprite};
+use crate::input::{Action, Binding, Input, MouseLook, assignable_key, back_pressed, binding_name, confirm_pressed, key_name, menu_adjust};
+use crate::saves::{slot_label, slot_path, SaveGame, SAVE_SLOTS};
+use crate::scores::{Entry, HighScores, Score, HIGH_SCORES_FILE};
+use crate::settings::{Settings, SETTINGS_FILE, RESOLUTIONS, Difficulty, FOV_RANGE, SENSITIVITY_RANGE, MAX_VOLUME, DEAD_ZONE_RANGE, PAD_ROTATION_RANGE};
+use crate::teleporters::teleport;
+use crate::textures::TextureSet;
+use crate::ui::{draw_minimap, draw_hud, draw_title_screen, draw_game_over_screen, draw_level_complete_screen, draw_level_select_screen, draw_message, draw_pause_screen, draw_save_slots_screen, draw_settings_screen, HudView, Menu, MinimapView};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    LevelComplete,
+    GameOver,
+    Settings,
+    LevelSelect,
+    SaveSlots(SlotAction),
+}
+
+// Qué hace el menú de ranuras al elegir una
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotAction {
+    Save,
+    Load,
+}
+
+// Lo que pide el estado actual al terminar el fotograma
+pub enum Transition {
+    Stay,
+    // Sustituye el estado actual
+    Switch(GameState),
+    // Apila un estado encima del actual (pausa, ajustes)
+    Push(GameState),
+    // Vuelve al estado anterior
+    Pop,
+    // Vacía la pila y deja solo este estado (salir al título desde la pausa)
+    Clear(GameState),
+    // Cierra el juego
+    Quit,
+}
+
+// Opciones del menú de pausa, en el orden en que se muestran
+const PAUSE_RESUME: usize = 0;
+const PAUSE_RESTART: usize = 1;
+const PAUSE_SAVE: usize = 2;
+const PAUSE_LOAD: usize = 3;
+const PAUSE_SETTINGS: usize = 4;
+const PAUSE_QUIT_TO_TITLE: usize = 5;
+
+pub fn pause_menu(screen_width: i32, screen_height: i32) -> Menu {
+    Menu::new(
+        &["Continuar", "Reiniciar nivel", "Guardar partida", "Cargar partida", "Ajustes", "Salir al título"],
+        screen_width / 2 - 180,
+        screen_height / 2 - 120,
+    )
+}
+
+// Una opción por ranura y, al final, "Volver"
+pub fn save_slots_menu() -> Menu {
+    let mut menu = Menu::new(&[], 80, 140);
+    menu.width = 640;
+    menu
+}
+
+// Opciones del menú de ajustes; detrás van las teclas y al final "Guardar y volver"
+const SETTING_RESOLUTION: usize = 0;
+const SETTING_FOV: usize = 1;
+const SETTING_SENSITIVITY: usize = 2;
+const SETTING_INVERT_MOUSE: usize = 3;
+const SETTING_MUSIC_VOLUME: usize = 4;
+const SETTING_SFX_VOLUME: usize = 5;
+const SETTING_DEAD_ZONE: usize = 6;
+const SETTING_PAD_ROTATION: usize = 7;
+const SETTING_DIFFICULTY: usize = 8;
+const SETTING_DAMAGE: usize = 9;
+const SETTING_CARRY_LIVES: usize = 10;
+const SETTING_FIRST_KEY: usize = 11;
+const SETTING_BACK: usize = SETTING_FIRST_KEY + Action::ALL.len();
+
+pub fn settings_menu() -> Menu {
+    let mut menu = Menu::new(&[], 80, 90);
+    menu.width = 520;
+    menu.item_height = 34;
+    menu.font_size = 20;
+    menu
+}
+
+// Niveles de la campaña y, al final, "Volver"
+pub fn level_select_menu() -> Menu {
+    let mut menu = Menu::new(&[], 80, 140);
+    menu.width = 640;
+    menu
+}
+
+// Pila de estados: solo el de arriba se actualiza y se dibuja
+pub struct StateMachine {
+    stack: Vec<GameState>,
+}
+
+impl StateMachine {
+    pub fn new(initial: GameState, game: &mut Game, rl: &mut RaylibHandle) -> Self {
+        game.enter(initial, rl);
+        Self { stack: vec![initial] }
+    }
+
+    pub fn current(&self) -> Option<GameState> {
+        self.stack.last().copied()
+    }
+
+    // Aplica la transición llamando a `exit` del estado que deja de estar arriba
+    // y a `enter` del que pasa a estarlo
+    pub fn apply(&mut self, transition: Transition, game: &mut Game, rl: &mut RaylibHandle) {
+        let previous = self.current();
+        match transition {
+            Transition::Stay => return,
+            Transition::Switch(state) => {
+                self.stack.pop();
+                self.stack.push(state);
+            }
+            Transition::Push(state) => self.stack.push(state),
+            Transition::Pop => {
+                self.stack.pop();
+            }
+            Transition::Clear(state) => {
+                self.stack.clear();
+                self.stack.push(state);
+            }
+            Transition::Quit => self.stack.clear(),
+        }
+        if let Some(state) = previous {
+            game.exit(state, rl);
+        }
+        if let Some(state) = self.current() {
+            game.enter(state, rl);
+        }
+    }
+}
+
+// Celdas alrededor del jugador que quedan descubiertas en el minimapa
+const EXPLORE_RADIUS: usize = 2;
+
+// Segundos que se muestran los avisos de la partida ("Necesitas la llave roja")
+const MESSAGE_TIME: f64 = 2.0;
+
+// Estado de una partida; `reset` lo devuelve todo al inicio del nivel
+pub struct Session {
+    pub player: Player,
+    pub damage: Damage,
+    pub damage_effect_time: f64,
+    pub invulnerability_time: f64,
+    // Segundos que quedan del aumento de velocidad
+    pub speed_boost_time: f64,
+    pub last_bump_time: f64,
+    pub show_hint: bool,
+    // Camino de la pista y la celda del jugador desde la que se calculó
+    pub hint: Option<(Cell, Option<Path>)>,
+    pub show_map: bool,
+    // Reloj de la partida: solo avanza en `Playing`, así la pausa congela efectos y animaciones
+    pub time: f64,
+    // Píxeles recorridos, para la eficiencia del recorrido en la puntuación
+    pub distance: f32,
+    // Monedas recogidas
+    pub collected: u32,
+    // Celdas ya vistas, por fila y columna; el resto del minimapa queda en niebla
+    pub explored: Vec<Vec<bool>>,
+    // Llaves que siguen en el suelo
+    pub keys: Vec<Key>,
+    // Objetos que siguen en el suelo
+    pub pickups: Vec<Pickup>,
+    // Celda a la que se llegó por un teletransporte; no se activa hasta salir de ella
+    pub teleport_lock: Option<Cell>,
+    // Aviso en pantalla y hasta qué momento de `time` se muestra
+    pub message: Option<(String, f64)>,
+    pub enemies: Vec<Enemy>,
+}
+
+impl Session {
+    pub fn new(maze: &Maze, block_size: i32, damage: Damage) -> Self {
+        let mut session = Self {
+            player: Player::new(0.0, 0.0),
+            damage: damage.clone(),
+            damage_effect_time: 0.0,
+            invulnerability_time: 0.0,
+            speed_boost_time: 0.0,
+            last_bump_time: 0.0,
+            show_hint: false,
+            hint: None,
+            show_map: true,
+            time: 0.0,
+            distance: 0.0,
+            collected: 0,
+            explored: Vec::new(),
+            keys: Vec::new(),
+            pickups: Vec::new(),
+            teleport_lock: None,
+            message: None,
+            enemies: Vec::new(),
+        };
+        session.reset(maze, block_size, damage);
+        session
+    }
+
+    pub fn reset(&mut self, maze: &Maze, block_size: i32, damage: Damage) {
+        let (start_row, start_col) = maze.start;
+        self.player.max_health = damage.max_health;
+        self.player.reset_health();
+        self.player.inventory = Inventory::default();
+        self.damage = damage;
+        self.player.reset_position(start_col, start_row, maze.start_angle, block_size);
+        self.damage_effect_time = 0.0;
+        self.invulnerability_time = 0.0;
+        self.speed_boost_time = 0.0;
+        self.last_bump_time = 0.0;
+        self.show_hint = false;
+        self.hint = None;
+        self.show_map = true;
+        self.time = 0.0;
+        self.distance = 0.0;
+        self.collected = 0;
+        self.explored = maze.grid.iter().map(|line| vec![false; line.len()]).collect();
+        explore(&mut self.explored, maze.start);
+        self.keys = maze.keys.clone();
+        self.pickups = maze.pickups.clone();
+        self.teleport_lock = Some(maze.start);
+        self.message = None;
+        self.enemies = Enemy::from_maze(maze, block_size);
+    }
+
+    // Quita salud y activa el efecto de daño y la invulnerabilidad
+    fn hurt(&mut self, amount: f32) {
+        self.player.take_damage(amount);
+        self.damage_effect_time = 0.5; // Efecto de daño por 0.5 segundos
+        self.invulnerability_time = self.damage.invulnerability;
+    }
+
+    fn show_message(&mut self, text: String) {
+        self.message = Some((text, self.time + MESSAGE_TIME));
+    }
+
+    // Aplica el efecto de un objeto; devuelve `false` si no se puede recoger todavía
+    fn collect(&mut self, kind: PickupKind) -> bool {
+        match kind {
+            PickupKind::Coin => self.collected += 1,
+            // Con la salud al máximo el corazón se queda en el suelo para más tarde
+            PickupKind::Heart => {
+                let player = &mut self.player;
+                if player.health >= player.max_health {
+                    return false;
+                }
+                player.health = (player.health + 1.0).min(player.max_health);
+                self.show_message("Has recuperado un corazón".to_string());
+            }
+            PickupKind::Invulnerability => {
+                self.invulnerability_time = self.invulnerability_time.max(INVULNERABILITY_TIME);
+                self.show_message(format!("Invulnerable durante {:.0} s", INVULNERABILITY_TIME));
+            }
+            PickupKind::Speed => {
+                self.speed_boost_time = SPEED_BOOST_TIME;
+                self.show_message(format!("Más velocidad durante {:.0} s", SPEED_BOOST_TIME));
+            }
+            PickupKind::Map => {
+                self.explored.iter_mut().for_each(|line| line.fill(true));
+                self.show_message("Mapa revelado".to_string());
+            }
+        }
+        true
+    }
+}
+
+// Decoraciones del nivel y llaves y objetos que quedan en el suelo
+fn level_sprites(maze: &Maze, session: &Session, block_size: i32) -> Vec<Sprite> {
+    let mut sprites = Sprite::from_decorations(maze, block_size);
+    sprites.extend(Sprite::from_keys(&session.keys, block_size));
+    sprites.extend(Sprite::from_pickups(&session.pickups, block_size));
+    sprites
+}
+
+// Abre o cierra la primera puerta que hay delante del jugador, a menos de un bloque
+fn interact_with_door(maze: &mut Maze, player: &Player, block_size: i32) -> Option<DoorAction> {
+    let size = block_size as f32;
+    let (sin, cos) = player.angle.sin_cos();
+    for step in 1..=8 {
+        let reach = size * step as f32 / 8.0;
+        let (row, col) = cell_at(player.x + cos * reach, player.y + sin * reach, block_size);
+        if let Some(door) = maze.door_mut(row, col) {
+            // El círculo del jugador toca la celda de la puerta
+            let (left, top) = (col as f32 * size, row as f32 * size);
+            let offset_x = player.x - player.x.clamp(left, left + size);
+            let offset_y = player.y - player.y.clamp(top, top + size);
+            let occupied = offset_x * offset_x + offset_y * offset_y < player.radius * player.radius;
+            return Some(door.interact(&player.inventory, occupied));
+        }
+        if maze.is_wall_cell(row, col) {
+            return None;
+        }
+    }
+    None
+}
+
+// Descubre las celdas alrededor de `cell` en el minimapa
+fn explore(explored: &mut [Vec<bool>], (row, col): Cell) {
+    for r in row.saturating_sub(EXPLORE_RADIUS)..=row + EXPLORE_RADIUS {
+        let Some(line) = explored.get_mut(r) else {
+            break;
+        };
+        for c in col.saturating_sub(EXPLORE_RADIUS)..=col + EXPLORE_RADIUS {
+            if let Some(seen) = line.get_mut(c) {
+                *seen = true;
+            }
+        }
+    }
+}
+
+pub struct Game {
+    pub maze: Maze,
+    pub textures: TextureSet,
+    pub sprites: Vec<Sprite>,
+    pub framebuffer: Framebuffer,
+    pub session: Session,
+    pub par: Option<f32>,
+    pub audio: Audio,
+    pub settings: Settings,
+    pub block_size: i32,
+    pub pause_menu: Menu,
+    pub settings_menu: Menu,
+    // Acción que espera a que se pulse su nueva tecla en el menú de ajustes
+    pub rebinding: Option<Action>,
+    pub input: Input,
+    pub render_threads: usize,
+    pub high_scores: HighScores,
+    // Puntuación del último nivel completado y su puesto en la tabla, si entró
+    pub score: Option<Score>,
+    pub score_rank: Option<usize>,
+    // `None` al jugar un nivel suelto pasado por la línea de comandos
+    pub campaign: Option<Campaign>,
+    pub level_select_menu: Menu,
+    // De dónde sale el nivel actual, para guardarlo en las partidas
+    pub level: LevelSource,
+    pub save_slots_menu: Menu,
+    // Resultado de la última vez que se guardó o cargó en el menú de ranuras
+    pub slot_message: Option<String>,
+}
+
+impl Game {
+    fn center_mouse(&self, rl: &mut RaylibHandle) {
+        rl.set_mouse_position((self.settings.screen_width as f32 / 2.0, self.settings.screen_height as f32 / 2.0));
+    }
+
+    fn reset_session(&mut self) {
+        self.maze.close_doors();
+        self.session.reset(&self.maze, self.block_size, Damage::resolve(&self.settings, &self.maze));
+        self.sprites = level_sprites(&self.maze, &self.session, self.block_size);
+    }
+
+    // Cambia al nivel `index` de la campaña; si no se puede cargar se avisa y se sigue en el actual
+    fn load_level(&mut self, index: usize, rl: &mut RaylibHandle, thread: &RaylibThread) -> bool {
+        let Some(campaign) = &mut self.campaign else {
+            return false;
+        };
+        let maze = match campaign.load_level(index) {
+            Ok(maze) => maze,
+            Err(err) => {
+                eprintln!("Error cargando el nivel {}", err);
+                return false;
+            }
+        };
+        campaign.playing = index;
+        campaign.progress.current = index;
+        if let Err(err) = campaign.progress.save(PROGRESS_FILE) {
+            eprintln!("No se pudo guardar el progreso en {}", err);
+        }
+        let level = LevelSource::File(campaign.levels[index].path.clone());
+        self.set_level(maze, level, rl, thread);
+        true
+    }
+
+    fn set_level(&mut self, maze: Maze, level: LevelSource, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.par = par_time(&maze, self.session.player.speed, self.block_size);
+        rl.set_window_title(thread, &format!("Laberinto Raycasting 3D - {}", maze.name));
+        self.maze = maze;
+        self.level = level;
+        self.reset_session();
+    }
+
+    fn save_game(&self, slot: usize) -> Result<(), String> {
+        SaveGame::from_session(&self.session, &self.maze, &self.level).save(&slot_path(slot))
+    }
+
+    // Carga el nivel de la partida y la restaura encima. En la campaña solo se aceptan sus niveles,
+    // para que completarlo siga desbloqueando el siguiente
+    fn load_game(&mut self, slot: usize, rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<(), String> {
+        let save = SaveGame::load(&slot_path(slot))?;
+        match (&self.campaign, &save.level) {
+            (Some(campaign), LevelSource::File(path)) => {
+                let index = campaign
+                    .levels
+                    .iter()
+                    .position(|level| level.path == *path)
+                    .ok_or_else(|| format!("el nivel {} no es de esta campaña", path))?;
+                if !self.load_level(index, rl, thread) {
+                    return Err(format!("no se pudo cargar el nivel {}", path));
+                }
+            }
+            (Some(_), LevelSource::Generated { .. }) => return Err("la partida es de un nivel generado, no de la campaña".to_string()),
+            (None, level) => {
+                let maze = level.load()?;
+                self.set_level(maze, level.clone(), rl, thread);
+            }
+        }
+        for warning in save.apply(&mut self.session, &mut self.maze, self.block_size) {
+            eprintln!("{}: {}", slot_path(slot), warning);
+        }
+        self.sprites = level_sprites(&self.maze, &self.session, self.block_size);
+        Ok(())
+    }
+
+    // Pasa al siguiente nivel de la campaña; con `conservar_vidas` se mantiene la salud
+    fn start_next_level(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> bool {
+        let Some(next) = self.campaign.as_ref().and_then(Campaign::next_level) else {
+            return false;
+        };
+        let health = self.session.player.health;
+        if !self.load_level(next, rl, thread) {
+            return false;
+        }
+        if self.settings.carry_lives {
+            let player = &mut self.session.player;
+            player.health = health.min(player.max_health);
+        }
+        true
+    }
+
+    // Calcula la puntuación del nivel recién completado y la guarda en la tabla
+    fn finish_level(&mut self) {
+        let session = &self.session;
+        let player = &session.player;
+        let score = Score::new(session.time as f32, self.par, player.health, session.collected, session.distance, player.speed);
+        self.score_rank = self.high_scores.insert(&self.maze.name, Entry { points: score.total(), time: score.time });
+        self.score = Some(score);
+        if self.score_rank.is_some()
+            && let Err(err) = self.high_scores.save(HIGH_SCORES_FILE)
+        {
+            eprintln!("No se pudieron guardar las puntuaciones en {}", err);
+        }
+
+        if let Some(campaign) = &mut self.campaign {
+            campaign.complete_level();
+            if let Err(err) = campaign.progress.save(PROGRESS_FILE) {
+                eprintln!("No se pudo guardar el progreso en {}", err);
+            }
+        }
+    }
+
+    pub fn enter(&mut self, state: GameState, rl: &mut RaylibHandle) {
+        match state {
+            GameState::Title => self.reset_session(),
+            GameState::LevelComplete => self.finish_level(),
+            // Centrar el ratón evita un giro brusco con el desplazamiento acumulado fuera de la partida
+            GameState::Playing => {
+                rl.disable_cursor();
+                self.center_mouse(rl);
+            }
+            GameState::Paused => {
+                self.pause_menu.selected = PAUSE_RESUME;
+                rl.enable_cursor();
+            }
+            GameState::Settings => {
+                self.settings_menu.selected = 0;
+                self.rebinding = None;
+                self.refresh_settings_menu();
+            }
+            GameState::LevelSelect => {
+                self.refresh_level_select_menu();
+                let playing = self.campaign.as_ref().map_or(0, |campaign| campaign.playing);
+                self.level_select_menu.selected = playing;
+                self.level_select_menu.scroll_to_selected();
+            }
+            GameState::SaveSlots(_) => {
+                self.save_slots_menu.selected = 0;
+                self.slot_message = None;
+                self.refresh_save_slots_menu();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn exit(&mut self, state: GameState, rl: &mut RaylibHandle) {
+        match state {
+            // El ratón queda libre fuera de la partida
+            GameState::Playing => rl.enable_cursor(),
+            GameState::Settings => {
+                if let Err(err) = self.settings.save(SETTINGS_FILE) {
+                    eprintln!("No se pudieron guardar los ajustes en {}", err);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Actualiza y dibuja un fotograma del estado indicado
+    pub fn run_state(&mut self, state: GameState, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        match state {
+            GameState::Title => self.title(rl, thread),
+            GameState::Playing => self.playing(rl, thread),
+            GameState::Paused => self.paused(rl, thread),
+            GameState::LevelComplete => self.level_complete(rl, thread),
+            GameState::GameOver => self.game_over(rl, thread),
+            GameState::Settings => self.settings(rl, thread),
+            GameState::LevelSelect => self.level_select(rl, thread),
+            GameState::SaveSlots(action) => self.save_slots(action, rl, thread),
+        }
+    }
+
+    fn title(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let enter_pressed = confirm_pressed(rl);
+        let settings_pressed = rl.is_key_pressed(KeyboardKey::KEY_O);
+        let level_select_pressed = rl.is_key_pressed(KeyboardKey::KEY_N);
+        let load_pressed = rl.is_key_pressed(KeyboardKey::KEY_C);
+        let quit_pressed = back_pressed(rl);
+        let level_label = self.campaign.as_ref().map(|campaign| {
+            format!("Nivel {}/{}: {}", campaign.playing + 1, campaign.levels.len(), self.maze.name)
+        });
+        let mut d = rl.begin_drawing(thread);
+        draw_title_screen(&mut d, self.par, &self.session.damage, &self.settings.bindings, self.high_scores.level(&self.maze.name), level_label.as_deref());
+        drop(d);
+        if enter_pressed {
+            Transition::Switch(GameState::Playing)
+        } else if quit_pressed {
+            Transition::Quit
+        } else if settings_pressed {
+            Transition::Push(GameState::Settings)
+        } else if level_select_pressed && self.campaign.is_some() {
+            Transition::Push(GameState::LevelSelect)
+        } else if load_pressed {
+            Transition::Push(GameState::SaveSlots(SlotAction::Load))
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn game_over(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let enter_pressed = confirm_pressed(rl);
+        let mut d = rl.begin_drawing(thread);
+        draw_game_over_screen(&mut d);
+        drop(d);
+        if enter_pressed { Transition::Switch(GameState::Title) } else { Transition::Stay }
+    }
+
+    fn level_complete(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let enter_pressed = confirm_pressed(rl);
+        let next_label = match &self.campaign {
+            Some(campaign) if campaign.next_level().is_some() => "Presiona ENTER para el siguiente nivel",
+            Some(_) => "¡Campaña completada! ENTER: volver al título",
+            None => "Presiona ENTER para reiniciar",
+        };
+        let mut d = rl.begin_drawing(thread);
+        if let Some(score) = &self.score {
+            draw_level_complete_screen(&mut d, score, self.score_rank, self.high_scores.level(&self.maze.name), next_label);
+        }
+        drop(d);
+        if !enter_pressed {
+            return Transition::Stay;
+        }
+        if self.start_next_level(rl, thread) {
+            Transition::Switch(GameState::Playing)
+        } else {
+            Transition::Switch(GameState::Title)
+        }
+    }
+
+    // Los niveles bloqueados se ven pero no se pueden elegir
+    fn level_select(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let escape_pressed = back_pressed(rl);
+        let selected = self.level_select_menu.update(rl);
+        let mut transition = Transition::Stay;
+
+        let level_count = self.campaign.as_ref().map_or(0, |campaign| campaign.levels.len());
+        match selected {
+            Some(index) if index == level_count => transition = Transition::Pop,
+            Some(index) => {
+                let unlocked = self.campaign.as_ref().is_some_and(|campaign| campaign.is_unlocked(index));
+                if unlocked && self.load_level(index, rl, thread) {
+                    transition = Transition::Clear(GameState::Playing);
+                }
+            }
+            None => {}
+        }
+        if escape_pressed {
+            transition = Transition::Pop;
+        }
+
+        let mut d = rl.begin_drawing(thread);
+        draw_level_select_screen(&mut d, &self.level_select_menu);
+        drop(d);
+        transition
+    }
+
+    fn refresh_level_select_menu(&mut self) {
+        let Some(campaign) = &self.campaign else {
+            return;
+        };
+        let mut items = Vec::new();
+        for (index, level) in campaign.levels.iter().enumerate() {
+            let item = if !campaign.is_unlocked(index) {
+                format!("{}. ??? (bloqueado)", index + 1)
+            } else {
+                match self.high_scores.level(&level.name).first() {
+                    Some(best) => format!("{}. {}  - récord {}", index + 1, level.name, best.points),
+                    None => format!("{}. {}", index + 1, level.name),
+                }
+            };
+            items.push(item);
+        }
+        items.push("Volver".to_string());
+        self.level_select_menu.items = items;
+        let menu = &mut self.level_select_menu;
+        menu.max_visible = ((self.settings.screen_height - menu.y - 60) / menu.item_height).max(1) as usize;
+    }
+
+    // El último fotograma de la partida queda congelado detrás del menú; como el reloj
+    // de la sesión no avanza, los efectos y animaciones siguen donde estaban
+    fn paused(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        self.input.update(rl, &self.settings.bindings, self.settings.dead_zone, None);
+        let escape_pressed = back_pressed(rl) || self.input.pressed(Action::Pause);
+        let chosen = self.pause_menu.update(rl);
+        let mut d = rl.begin_drawing(thread);
+        self.framebuffer.draw_to_screen(&mut d);
+        draw_pause_screen(&mut d, &self.pause_menu, self.settings.screen_width, self.settings.screen_height);
+        drop(d);
+
+        if escape_pressed {
+            return Transition::Pop;
+        }
+        match chosen {
+            Some(PAUSE_RESUME) => Transition::Pop,
+            Some(PAUSE_RESTART) => {
+                self.reset_session();
+                Transition::Pop
+            }
+            Some(PAUSE_SAVE) => Transition::Push(GameState::SaveSlots(SlotAction::Save)),
+            Some(PAUSE_LOAD) => Transition::Push(GameState::SaveSlots(SlotAction::Load)),
+            Some(PAUSE_SETTINGS) => Transition::Push(GameState::Settings),
+            Some(PAUSE_QUIT_TO_TITLE) => Transition::Clear(GameState::Title),
+            _ => Transition::Stay,
+        }
+    }
+
+    // Al guardar se sigue en el menú para ver la ranura actualizada; al cargar se vuelve a la partida
+    fn save_slots(&mut self, action: SlotAction, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let escape_pressed = back_pressed(rl);
+        let selected = self.save_slots_menu.update(rl);
+        let mut transition = Transition::Stay;
+
+        match selected {
+            Some(SAVE_SLOTS) => transition = Transition::Pop,
+            Some(slot) => match action {
+                SlotAction::Save => {
+                    self.slot_message = Some(match self.save_game(slot) {
+                        Ok(()) => format!("Partida guardada en la ranura {}", slot + 1),
+                        Err(err) => format!("No se pudo guardar: {}", err),
+                    });
+                    self.refresh_save_slots_menu();
+                }
+                SlotAction::Load => match self.load_game(slot, rl, thread) {
+                    Ok(()) => transition = Transition::Clear(GameState::Playing),
+                    Err(err) => self.slot_message = Some(format!("No se pudo cargar: {}", err)),
+                },
+            },
+            None => {}
+        }
+        if escape_pressed {
+            transition = Transition::Pop;
+        }
+
+        let title = match action {
+            SlotAction::Save => "GUARDAR PARTIDA",
+            SlotAction::Load => "CARGAR PARTIDA",
+        };
+        let mut d = rl.begin_drawing(thread);
+        draw_save_slots_screen(&mut d, &self.save_slots_menu, title, self.slot_message.as_deref());
+        drop(d);
+        transition
+    }
+
+    fn refresh_save_slots_menu(&mut self) {
+        let mut items: Vec<String> = (0..SAVE_SLOTS).map(slot_label).collect();
+        items.push("Volver".to_string());
+        self.save_slots_menu.items = items;
+    }
+
+    fn settings(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let escape_pressed = back_pressed(rl);
+        let mut transition = Transition::Stay;
+
+        if let Some(action) = self.rebinding {
+            // Esperando la nueva tecla de un control
+            if escape_pressed {
+                self.rebinding = None;
+            } else if let Some(key) = assignable_key(rl) {
+                self.settings.bindings.set_key(action, key);
+                self.rebinding = None;
+            }
+        } else {
+            let step = menu_adjust(rl);
+            let selected = self.settings_menu.selected;
+            if step != 0 && selected < SETTING_FIRST_KEY {
+                self.change_setting(selected, step, rl);
+            }
+            match self.settings_menu.update(rl) {
+                Some(SETTING_BACK) => transition = Transition::Pop,
+                Some(index) if index >= SETTING_FIRST_KEY => self.rebinding = Some(Action::ALL[index - SETTING_FIRST_KEY]),
+                Some(index) => self.change_setting(index, 1, rl),
+                None => {}
+            }
+            if escape_pressed {
+                transition = Transition::Pop;
+            }
+        }
+
+        self.refresh_settings_menu();
+        let mut d = rl.begin_drawing(thread);
+        draw_settings_screen(&mut d, &self.settings_menu, self.rebinding.is_some());
+        drop(d);
+        transition
+    }
+
+    // Cambia un ajuste un paso hacia delante o hacia atrás y lo aplica en el momento
+    fn change_setting(&mut self, index: usize, step: i32, rl: &mut RaylibHandle) {
+        let settings = &mut self.settings;
+        match index {
+            SETTING_RESOLUTION => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|&size| size == (settings.screen_width, settings.screen_height))
+                    .unwrap_or(1);
+                let (width, height) = RESOLUTIONS[cycle(current, step, RESOLUTIONS.len())];
+                settings.screen_width = width;
+                settings.screen_height = height;
+                rl.set_window_size(width, height);
+                self.framebuffer = Framebuffer::new(width as u32, height as u32);
+                self.pause_menu = pause_menu(width, height);
+            }
+            SETTING_FOV => settings.fov_degrees = (settings.fov_degrees + 5.0 * step as f32).clamp(FOV_RANGE.0, FOV_RANGE.1),
+            SETTING_SENSITIVITY => {
+                settings.mouse_sensitivity = (settings.mouse_sensitivity + 0.0005 * step as f32).clamp(SENSITIVITY_RANGE.0, SENSITIVITY_RANGE.1);
+            }
+            SETTING_INVERT_MOUSE => settings.invert_mouse = !settings.invert_mouse,
+            SETTING_MUSIC_VOLUME => {
+                settings.music_volume = (settings.music_volume as i32 + 10 * step).clamp(0, MAX_VOLUME as i32) as u32;
+                self.audio.set_music_volume(settings.music_volume);
+            }
+            SETTING_SFX_VOLUME => {
+                settings.sfx_volume = (settings.sfx_volume as i32 + 10 * step).clamp(0, MAX_VOLUME as i32) as u32;
+                self.audio.set_sfx_volume(settings.sfx_volume);
+                self.audio.play_effect(BUMP_SOUND);
+            }
+            SETTING_DEAD_ZONE => settings.dead_zone = (settings.dead_zone + 0.05 * step as f32).clamp(DEAD_ZONE_RANGE.0, DEAD_ZONE_RANGE.1),
+            SETTING_PAD_ROTATION => {
+                settings.pad_rotation_speed = (settings.pad_rotation_speed + 0.25 * step as f32).clamp(PAD_ROTATION_RANGE.0, PAD_ROTATION_RANGE.1);
+            }
+            SETTING_DIFFICULTY => {
+                let current = Difficulty::ALL.iter().position(|&d| d == settings.difficulty).unwrap_or(1);
+                settings.difficulty = Difficulty::ALL[cycle(current, step, Difficulty::ALL.len())];
+            }
+            SETTING_DAMAGE => {
+                // Auto seguido de las políticas
+                let options: Vec<Option<DamagePolicy>> = std::iter::once(None).chain(DamagePolicy::ALL.map(Some)).collect();
+                let current = options.iter().position(|&policy| policy == settings.damage).unwrap_or(0);
+                settings.damage = options[cycle(current, step, options.len())];
+            }
+            SETTING_CARRY_LIVES => settings.carry_lives = !settings.carry_lives,
+            _ => {}
+        }
+    }
+
+    fn refresh_settings_menu(&mut self) {
+        let settings = &self.settings;
+        let mut items = vec![
+            format!("Resolución: {}x{}", settings.screen_width, settings.screen_height),
+            format!("Campo de visión: {:.0}°", settings.fov_degrees),
+            format!("Sensibilidad del ratón: {:.4}", settings.mouse_sensitivity),
+            format!("Invertir ratón: {}", if settings.invert_mouse { "Sí" } else { "No" }),
+            format!("Volumen música: {}", settings.music_volume),
+            format!("Volumen efectos: {}", settings.sfx_volume),
+            format!("Zona muerta del mando: {:.2}", settings.dead_zone),
+            format!("Giro con el mando: {:.2} rad/s", settings.pad_rotation_speed),
+            format!("Dificultad: {} (al reiniciar)", settings.difficulty.name()),
+            format!("Daño: {} (al reiniciar)", settings.damage.map_or("auto", DamagePolicy::name)),
+            format!("Conservar vidas entre niveles: {}", if settings.carry_lives { "Sí" } else { "No" }),
+        ];
+        for action in Action::ALL {
+            let key = if self.rebinding == Some(action) {
+                "...".to_string()
+            } else {
+                settings.bindings.key(action).map(key_name).unwrap_or("-").to_string()
+            };
+            // Los controles de ratón y mando se muestran pero solo se cambian en el archivo
+            let others: Vec<String> = settings
+                .bindings
+                .get(action)
+                .iter()
+                .filter(|binding| !matches!(binding, Binding::Key(_)))
+                .map(|binding| binding_name(*binding))
+                .collect();
+            let others = if others.is_empty() { String::new() } else { format!("  ({})", others.join(", ")) };
+            items.push(format!("{}: {}{}", action.label(), key, others));
+        }
+        items.push("Guardar y volver".to_string());
+        self.settings_menu.items = items;
+        // Las opciones que caben entre el título y la ayuda
+        self.settings_menu.max_visible = ((settings.screen_height - self.settings_menu.y - 40) / self.settings_menu.item_height).max(1) as usize;
+    }
+
+    fn playing(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Transition {
+        let fps = rl.get_fps();
+        let block_size = self.block_size;
+        let settings = &self.settings;
+        let mouse_look = MouseLook {
+            screen_width: settings.screen_width,
+            sensitivity: settings.mouse_sensitivity,
+            invert: settings.invert_mouse,
+        };
+        let input = &mut self.input;
+        input.update(rl, &settings.bindings, settings.dead_zone, Some(mouse_look));
+        let session = &mut self.session;
+        let player = &mut session.player;
+
+        // Delta de tiempo
+        let dt = rl.get_frame_time();
+        session.time += dt as f64;
+        let time = session.time;
+
+        // Actualizar efectos temporales
+        if session.damage_effect_time > 0.0 {
+            session.damage_effect_time -= dt as f64;
+        }
+        if session.invulnerability_time > 0.0 {
+            session.invulnerability_time -= dt as f64;
+        }
+        if session.speed_boost_time > 0.0 {
+            session.speed_boost_time -= dt as f64;
+        }
+        if session.message.as_ref().is_some_and(|(_, until)| time >= *until) {
+            session.message = None;
+        }
+        self.maze.update_doors(dt);
+
+        // Movimiento del jugador: avance y paso lateral; con stick es proporcional a la inclinación
+        let forward = input.axis(Action::MoveBack, Action::MoveForward);
+        let strafe = input.axis(Action::StrafeLeft, Action::StrafeRight);
+        let boost = if session.speed_boost_time > 0.0 { SPEED_BOOST } else { 1.0 };
+        let (dx, dy) = player.movement(forward, strafe, dt);
+        let (dx, dy) = (dx * boost, dy * boost);
+
+        // Giro con teclado o stick, cada uno a su velocidad (si se mueve el stick manda él), más el del ratón
+        let stick_turn = input.stick_axis(Action::TurnLeft, Action::TurnRight);
+        let turn = if stick_turn != 0.0 {
+            stick_turn * settings.pad_rotation_speed
+        } else {
+            input.axis(Action::TurnLeft, Action::TurnRight) * settings.key_rotation_speed
+        };
+        player.angle += turn * dt;
+        player.angle += input.look;
+
+        // Colisiones (el jugador es un círculo que se desliza por las paredes) y daño
+        let (previous_x, previous_y) = (player.x, player.y);
+        let movement = player.move_and_collide(&self.maze, dx, dy, block_size);
+        session.distance += ((player.x - previous_x).powi(2) + (player.y - previous_y).powi(2)).sqrt();
+
+        // Teletransportes y portales: al pisarlos se aparece en su destino
+        if let Some((x, y, angle)) = teleport(&self.maze, player.x, player.y, player.angle, &mut session.teleport_lock, block_size) {
+            (player.x, player.y, player.angle) = (x, y, angle);
+        }
+        explore(&mut session.explored, cell_at(player.x, player.y, block_size));
+
+        // Si hubo colisión y no está en período de invulnerabilidad, la política decide el daño.
+        // Las puertas no hacen daño: chocar con una que se está abriendo o con llave es normal
+        if let Some(wall) = movement.wall.filter(|&wall| !is_door_char(wall))
+            && session.invulnerability_time <= 0.0
+            && time - session.last_bump_time > 0.1
+        {
+            // Fracción del avance del frame que frenó la pared
+            let impact = movement.impact / (player.speed * boost * dt).max(f32::EPSILON);
+            let damage = session.damage.amount(wall, impact);
+            if damage > 0.0 {
+                // Reproducir sonido
+                self.audio.play_effect(BUMP_SOUND);
+                session.last_bump_time = time;
+
+                // Perder salud y activar efectos
+                session.hurt(damage);
+
+                // Verificar si se acabó la salud
+                if !session.player.is_alive() {
+                    return Transition::Switch(GameState::GameOver);
+                }
+            }
+        }
+
+        // Puertas: se abren y se cierran con la acción de interactuar
+        if input.pressed(Action::Interact) {
+            match interact_with_door(&mut self.maze, &session.player, block_size) {
+                Some(DoorAction::Locked(color)) => session.show_message(format!("Necesitas la llave {}", color.name())),
+                Some(DoorAction::Blocked) => session.show_message("Sal de la puerta para cerrarla".to_string()),
+                _ => {}
+            }
+        }
+
+        // Llaves: se recogen al pasar por encima
+        let player = &session.player;
+        let picked: Vec<Key> = session.keys.extract_if(.., |key| in_reach(player, (key.row, key.col), block_size)).collect();
+        for key in &picked {
+            session.player.inventory.add_key(key.color);
+            session.show_message(format!("Has recogido la llave {}", key.color.name()));
+            self.audio.play_effect(PICKUP_SOUND);
+        }
+
+        // Objetos: igual que las llaves, pero cada uno tiene su efecto y alguno, como el corazón
+        // con la salud llena, se queda en el suelo. Se sacan de la sesión mientras tanto porque
+        // `collect` la modifica
+        let mut pickups = std::mem::take(&mut session.pickups);
+        let collected: Vec<Pickup> = pickups
+            .extract_if(.., |pickup| in_reach(&session.player, (pickup.row, pickup.col), block_size) && session.collect(pickup.kind))
+            .collect();
+        session.pickups = pickups;
+        for _ in &collected {
+            self.audio.play_effect(PICKUP_SOUND);
+        }
+        if !picked.is_empty() || !collected.is_empty() {
+            self.sprites = level_sprites(&self.maze, session, block_size);
+        }
+
+        // Enemigos: patrullan, persiguen al jugador mientras lo ven y al tocarlo le hacen daño
+        for enemy in &mut session.enemies {
+            enemy.update(&self.maze, &session.player, dt, block_size);
+        }
+        if session.invulnerability_time <= 0.0 && session.enemies.iter().any(|enemy| enemy.touches(&session.player)) {
+            let damage = session.damage.enemy_amount();
+            if damage > 0.0 {
+                self.audio.play_effect(BUMP_SOUND);
+                session.hurt(damage);
+                if !session.player.is_alive() {
+                    return Transition::Switch(GameState::GameOver);
+                }
+            }
+        }
+        let player = &mut session.player;
+
+        if input.pressed(Action::ToggleHint) {
+            session.show_hint = !session.show_hint;
+        }
+        if input.pressed(Action::ToggleMap) {
+            session.show_map = !session.show_map;
+        }
+
+        // Detectar llegada a la meta
+        let player_cell = self.maze.get_maze_cell(player.x, player.y, block_size);
+        if player_cell == 'E' {
+            return Transition::Switch(GameState::LevelComplete);
+        }
+
+        let (screen_width, screen_height) = (settings.screen_width, settings.screen_height);
+        let framebuffer = &mut self.framebuffer;
+
+        // Renderizar escena 3D en nuestro framebuffer personalizado; los enemigos se mueven, así que
+        // sus sprites se rehacen en cada fotograma
+        let sprites = [self.sprites.as_slice(), &Sprite::from_enemies(&session.enemies)].concat();
+        let view = SceneView {
+            fov: settings.fov(),
+            block_size,
+            time,
+            invulnerability_time: session.invulnerability_time,
+            screen_width,
+            screen_height,
+            threads: self.render_threads,
+        };
+        render_scene(framebuffer, &self.maze, &self.textures, &sprites, player, view);
+
+        // Aplicar efecto de daño directamente al framebuffer si es necesario
+        if session.damage_effect_time > 0.0 {
+            let intensity = (session.damage_effect_time / 0.5 * 100.0) as u8;
+            let damage_color = Color::new(255, intensity, intensity, 255);
+
+            // Aplicar efecto de daño a todo el framebuffer
+            for y in 0..framebuffer.height {
+                for x in 0..framebuffer.width {
+                    let current_pixel = framebuffer.get_pixel(x, y);
+                    let mixed_color = Color::new(
+                        ((current_pixel.r as u16 + damage_color.r as u16) / 2) as u8,
+                        ((current_pixel.g as u16 + damage_color.g as u16) / 2) as u8,
+                        ((current_pixel.b as u16 + damage_color.b as u16) / 2) as u8,
+                        255,
+                    );
+                    framebuffer.set_pixel_color(x, y, mixed_color);
+                }
+            }
+        }
+
+        // Actualizar la textura del framebuffer
+        framebuffer.swap_buffers(rl, thread);
+
+        let pause_pressed = input.pressed(Action::Pause);
+        let mut d = rl.begin_drawing(thread);
+
+        // Dibujar nuestro framebuffer a la pantalla
+        framebuffer.draw_to_screen(&mut d);
+
+        // --- EFECTO DE LINTERNA (usando anillos para no cubrir el centro) ---
+        let light_radius = (screen_height as f32 * 0.45) as i32;
+        let center = Vector2::new((screen_width / 2) as f32, (screen_height / 2) as f32);
+        let max_r = screen_width.max(screen_height);
+        let step = 12;
+        for r in (light_radius..max_r).step_by(step as usize) {
+            let alpha = ((r - light_radius) as f32 / (screen_height as f32 * 0.55)).clamp(0.0, 1.0);
+            let darkness = (180.0 * alpha) as u8;
+            d.draw_ring(center, r as f32, (r + step) as f32, 0.0, 360.0, 64, Color::new(0, 0, 0, darkness));
+        }
+
+        // Minimapa con la pista opcional hacia la salida. El camino solo se recalcula al cambiar de
+        // celda: las puertas siempre cuentan como transitables, así que abrirlas no lo cambia
+        if session.show_map {
+            let cell = cell_at(player.x, player.y, block_size);
+            if session.show_hint && session.hint.as_ref().is_none_or(|(from, _)| *from != cell) {
+                let path = self.maze.exit.and_then(|exit| find_path(&self.maze, cell, exit, Algorithm::AStar));
+                session.hint = Some((cell, path));
+            }
+            let hint = session.hint.as_ref().filter(|_| session.show_hint).and_then(|(_, path)| path.as_ref());
+            let view = MinimapView {
+                explored: &session.explored,
+                enemies: &session.enemies,
+                hint,
+                block_size,
+                screen_width,
+                time,
+                invulnerability_time: session.invulnerability_time,
+            };
+            draw_minimap(&mut d, &self.maze, player, view);
+        }
+
+        // HUD
+        let coins = self.maze.pickups.iter().filter(|pickup| pickup.kind == PickupKind::Coin).count() as u32;
+        let coins = (coins > 0).then_some((session.collected, coins));
+        let view = HudView {
+            invulnerability_time: session.invulnerability_time,
+            speed_boost_time: session.speed_boost_time,
+            coins,
+            current_time: time,
+            fps,
+            screen_width,
+        };
+        draw_hud(&mut d, player, &session.damage, &self.settings.bindings, view);
+        if let Some((message, _)) = &session.message {
+            draw_message(&mut d, message, screen_width, screen_height);
+        }
+        drop(d);
+
+        if pause_pressed { Transition::Push(GameState::Paused) } else { Transition::Stay }
+    }
+}
+
+// Avanza `step` posiciones en una lista circular de `len` elementos
+fn cycle(index: usize, step: i32, len: usize) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+use crate::maze::Maze;
+use crate::pathfinding::distance_map;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Backtracker,
+    Prim,
+    Kruskal,
+    Wilson,
+    Rooms,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 5] = [
+        Algorithm::Backtracker,
+        Algorithm::Prim,
+        Algorithm::Kruskal,
+        Algorithm::Wilson,
+        Algorithm::Rooms,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Backtracker => "backtracker",
+            Algorithm::Prim => "prim",
+            Algorithm::Kruskal => "kruskal",
+            Algorithm::Wilson => "wilson",
+            Algorithm::Rooms => "salas",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        Algorithm::ALL.iter().copied().find(|algorithm| algorithm.name() == name)
+    }
+}
+
+// Generador pseudoaleatorio SplitMix64: misma semilla, mismo laberinto en cualquier plataforma
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Entero uniforme en 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+// Cuadrícula de trabajo: las celdas lógicas viven en coordenadas impares
+// y las paredes entre ellas en las posiciones pares intermedias.
+struct Carver {
+    open: Vec<Vec<bool>>,
+    cells_w: usize,
+    cells_h: usize,
+}
+
+impl Carver {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            open: vec![vec![false; width]; height],
+            cells_w: (width - 1) / 2,
+            cells_h: (height - 1) / 2,
+        }
+    }
+
+    fn cell_count(&self) -> usize {
+        self.cells_w * self.cells_h
+    }
+
+    fn index(&self, cx: usize, cy: usize) -> usize {
+        cy * self.cells_w + cx
+    }
+
+    fn coords(&self, index: usize) -> (usize, usize) {
+        (index % self.cells_w, index / self.cells_w)
+    }
+
+    fn neighbors(&self, index: usize) -> Vec<usize> {
+        let (cx, cy) = self.coords(index);
+        let mut result = Vec::with_capacity(4);
+        if cx > 0 {
+            result.push(self.index(cx - 1, cy));
+        }
+        if cx + 1 < self.cells_w {
+            result.push(self.index(cx + 1, cy));
+        }
+        if cy > 0 {
+            result.push(self.index(cx, cy - 1));
+        }
+        if cy + 1 < self.cells_h {
+            result.push(self.index(cx, cy + 1));
+        }
+        result
+    }
+
+    fn open_cell(&mut self, index: usize) {
+        let (cx, cy) = self.coords(index);
+        self.open[cy * 2 + 1][cx * 2 + 1] = true;
+    }
+
+    // Abre ambas celdas y la pared que las separa
+    fn connect(&mut self, a: usize, b: usize) {
+        let (ax, ay) = self.coords(a);
+        let (bx, by) = self.coords(b);
+        self.open_cell(a);
+        self.open_cell(b);
+        self.open[ay + by + 1][ax + bx + 1] = true;
+    }
+
+    // Todas las paredes internas entre celdas vecinas
+    fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = Vec::new();
+        for cy in 0..self.cells_h {
+            for cx in 0..self.cells_w {
+                if cx + 1 < self.cells_w {
+                    edges.push((self.index(cx, cy), self.index(cx + 1, cy)));
+                }
+                if cy + 1 < self.cells_h {
+                    edges.push((self.index(cx, cy), self.index(cx, cy + 1)));
+                }
+            }
+        }
+        edges
+    }
+}
+
+// Conjuntos disjuntos para Kruskal y para las salas
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        Self { parent: (0..size).collect() }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let ra = self.find(a);
+        let rb = self.find(b);
+        if ra == rb {
+            return false;
+        }
+        self.parent[ra] = rb;
+        true
+    }
+}
+
+pub fn generate(algorithm: Algorithm, width: usize, height: usize, seed: u64) -> Maze {
+    // Las dimensiones deben ser impares para que haya borde en los cuatro lados
+    let width = (width.max(5) - 1) / 2 * 2 + 1;
+    let height = (height.max(5) - 1) / 2 * 2 + 1;
+    let mut rng = Rng::new(seed);
+    let mut carver = Carver::new(width, height);
+    let sets = DisjointSet::new(carver.cell_count());
+
+    match algorithm {
+        Algorithm::Backtracker => carve_backtracker(&mut carver, &mut rng),
+        Algorithm::Prim => carve_prim(&mut carver, &mut rng),
+        Algorithm::Kruskal => carve_kruskal(&mut carver, &mut rng, sets),
+        Algorithm::Wilson => carve_wilson(&mut carver, &mut rng),
+        Algorithm::Rooms => carve_rooms(&mut carver, &mut rng, sets),
+    }
+
+    let grid = paint_walls(&carver.open, &mut rng);
+    let mut maze = Maze {
+        name: format!("Generado ({}, semilla {})", algorithm.name(), seed),
+        grid,
+        start: (1, 1),
+        start_angle: 0.0,
+        exit: None,
+        decorations: Vec::new(),
+        damage: None,
+        hazards: Vec::new(),
+        doors: Vec::new(),
+        keys: Vec::new(),
+        enemies: Vec::new(),
+        pickups: Vec::new(),
+        teleporters: Vec::new(),
+        heights: HashMap::new(),
+    };
+
+    // Mirar hacia el primer pasillo abierto desde el inicio
+    maze.start_angle = if maze.is_wall_cell(1, 2) { std::f32::consts::FRAC_PI_2 } else { 0.0 };
+
+    let (exit_row, exit_col) = farthest_cell(&maze, maze.start);
+    maze.grid[exit_row][exit_col] = 'E';
+    maze.exit = Some((exit_row, exit_col));
+    maze
+}
+
+fn carve_backtracker(carver: &mut Carver, rng: &mut Rng) {
+    let mut visited = vec![false; carver.cell_count()];
+    let mut stack = vec![0];
+    visited[0] = true;
+    carver.open_cell(0);
+
+    while let Some(&current) = stack.last() {
+        let unvisited: Vec<usize> = carver
+            .neighbors(current)
+            .into_iter()
+            .filter(|&n| !visited[n])
+            .collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = unvisited[rng.below(unvisited.len())];
+        visited[next] = true;
+        carver.connect(current, next);
+        stack.push(next);
+    }
+}
+
+fn carve_prim(carver: &mut Carver, rng: &mut Rng) {
+    let mut in_maze = vec![false; carver.cell_count()];
+    let mut frontier: Vec<(usize, usize)> = Vec::new();
+    in_maze[0] = true;
+    carver.open_cell(0);
+    for n in carver.neighbors(0) {
+        frontier.push((0, n));
+    }
+
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.below(frontier.len()));
+        if in_maze[to] {
+            continue;
+        }
+        in_maze[to] = true;
+        carver.connect(from, to);
+        for n in carver.neighbors(to) {
+            if !in_maze[n] {
+                frontier.push((to, n));
+            }
+        }
+    }
+}
+
+fn carve_kruskal(carver: &mut Carver, rng: &mut Rng, mut sets: DisjointSet) {
+    let mut edges = carver.edges();
+    rng.shuffle(&mut edges);
+    for index in 0..carver.cell_count() {
+        carver.open_cell(index);
+    }
+    for (a, b) in edges {
+        if sets.union(a, b) {
+            carver.connect(a, b);
+        }
+    }
+}
+
+fn carve_wilson(carver: &mut Carver, rng: &mut Rng) {
+    let count = carver.cell_count();
+    let mut in_maze = vec![false; count];
+    let first = rng.below(count);
+    in_maze[first] = true;
+    carver.open_cell(first);
+    // Siguiente paso de cada celda durante el paseo; las vueltas se borran solas al sobrescribirse
+    let mut next_step = vec![usize::MAX; count];
+
+    for start in 0..count {
+        if in_maze[start] {
+            continue;
+        }
+        let mut current = start;
+        while !in_maze[current] {
+            let neighbors = carver.neighbors(current);
+            let next = neighbors[rng.below(neighbors.len())];
+            next_step[current] = next;
+            current = next;
+        }
+        let mut current = start;
+        while !in_maze[current] {
+            in_maze[current] = true;
+            carver.connect(current, next_step[current]);
+            current = next_step[current];
+        }
+    }
+}
+
+fn carve_rooms(carver: &mut Carver, rng: &mut Rng, mut sets: DisjointSet) {
+    let mut rooms: Vec<(usize, usize, usize, usize)> = Vec::new();
+    let attempts = carver.cell_count() / 4 + 4;
+
+    for _ in 0..attempts {
+        if carver.cells_w < 3 || carver.cells_h < 3 {
+            break;
+        }
+        let room_w = 2 + rng.below(3.min(carver.cells_w - 2));
+        let room_h = 2 + rng.below(3.min(carver.cells_h - 2));
+        let x = rng.below(carver.cells_w - room_w + 1);
+        let y = rng.below(carver.cells_h - room_h + 1);
+        // Las salas no se tocan para que siempre quede un pasillo entre ellas
+        let overlaps = rooms.iter().any(|&(rx, ry, rw, rh)| {
+            x <= rx + rw && rx <= x + room_w && y <= ry + rh && ry <= y + room_h
+        });
+        if overlaps {
+            continue;
+        }
+        rooms.push((x, y, room_w, room_h));
+
+        for cy in y..y + room_h {
+            for cx in x..x + room_w {
+                let index = carver.index(cx, cy);
+                if cx + 1 < x + room_w {
+                    let right = carver.index(cx + 1, cy);
+                    carver.connect(index, right);
+                    sets.union(index, right);
+                }
+                if cy + 1 < y + room_h {
+                    let below = carver.index(cx, cy + 1);
+                    carver.connect(index, below);
+                    sets.union(index, below);
+                }
+                // Rellenar el pilar central entre cuatro celdas de la sala
+                if cx + 1 < x + room_w && cy + 1 < y + room_h {
+                    carver.open[cy * 2 + 2][cx * 2 + 2] = true;
+                }
+            }
+        }
+    }
+
+    // Los pasillos unen las salas (ya fusionadas) con el resto como un árbol
+    carve_kruskal(carver, rng, sets);
+}
+
+// Asigna las letras A-D según la región: cada muro toma la letra del centro más cercano
+fn paint_walls(open: &[Vec<bool>], rng: &mut Rng) -> Vec<Vec<char>> {
+    let height = open.len();
+    let width = open[0].len();
+    let letters = ['A', 'B', 'C', 'D'];
+    let centers: Vec<(usize, usize)> = letters
+        .iter()
+        .map(|_| (rng.below(height), rng.below(width)))
+        .collect();
+
+    open.iter()
+        .enumerate()
+        .map(|(row, line)| {
+            line.iter()
+                .enumerate()
+                .map(|(col, &is_open)| {
+                    if is_open {
+                        return ' ';
+                    }
+                    let nearest = centers
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, (cr, cc))| {
+                            let dr = row as i64 - *cr as i64;
+                            let dc = col as i64 - *cc as i64;
+                            dr * dr + dc * dc
+                        })
+                        .map(|(i, _)| i)
+                        .unwrap_or(0);
+                    letters[nearest]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Celda transitable más lejana al inicio en número de pasos
+fn farthest_cell(maze: &Maze, start: (usize, usize)) -> (usize, usize) {
+    let distances = distance_map(maze, start);
+    let mut farthest = start;
+    let mut farthest_steps = 0;
+    for (row, line) in distances.iter().enumerate() {
+        for (col, steps) in line.iter().enumerate() {
+            if let Some(steps) = *steps
+                && steps > farthest_steps
+            {
+                farthest = (row, col);
+                farthest_steps = steps;
+            }
+        }
+    }
+    farthest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::{find_path, Algorithm as Search};
+    use crate::validator::validate;
+
+    const SEEDS: [u64; 4] = [0, 1, 42, 0xDEAD_BEEF];
+
+    #[test]
+    fn same_seed_gives_the_same_maze() {
+        for algorithm in Algorithm::ALL {
+            for seed in SEEDS {
+                let first = generate(algorithm, 21, 15, seed);
+                let second = generate(algorithm, 21, 15, seed);
+                assert_eq!(first.grid, second.grid, "{} con semilla {}", algorithm.name(), seed);
+                assert_eq!((first.start, first.exit), (second.start, second.exit));
+            }
+            // Y semillas distintas no dan siempre el mismo
+            let grids: Vec<_> = SEEDS.iter().map(|&seed| generate(algorithm, 21, 15, seed).grid).collect();
+            assert!(grids.iter().any(|grid| *grid != grids[0]), "{} ignora la semilla", algorithm.name());
+        }
+    }
+
+    #[test]
+    fn every_algorithm_connects_start_and_exit() {
+        for algorithm in Algorithm::ALL {
+            for seed in SEEDS {
+                let maze = generate(algorithm, 25, 19, seed);
+                let name = format!("{} con semilla {}", algorithm.name(), seed);
+                assert!(validate(&maze).is_empty(), "{}: {:?}", name, validate(&maze));
+                let exit = maze.exit.expect("sin salida");
+                assert!(find_path(&maze, maze.start, exit, Search::Bfs).is_some(), "{}", name);
+
+                // No quedan zonas abiertas aisladas
+                let distances = distance_map(&maze, maze.start);
+                for (row, line) in maze.grid.iter().enumerate() {
+                    for (col, &cell) in line.iter().enumerate() {
+                        if !maze.is_wall_cell(row, col) {
+                            assert!(distances[row][col].is_some(), "{}: ({}, {}) `{}` aislada", name, row, col, cell);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
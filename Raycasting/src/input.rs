@@ -0,0 +1,406 @@
+use raylib::prelude::*;
+
+// Mando que se lee; raylib numera los mandos conectados desde 0
+const GAMEPAD: i32 = 0;
+
+// Una acción analógica cuenta como pulsada a partir de este valor
+const PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    Interact,
+    Pause,
+    ToggleMap,
+    ToggleHint,
+}
+
+const ACTION_COUNT: usize = 10;
+
+impl Action {
+    pub const ALL: [Action; ACTION_COUNT] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Interact,
+        Action::Pause,
+        Action::ToggleMap,
+        Action::ToggleHint,
+    ];
+
+    // Nombre en el archivo de ajustes (`control_<nombre>`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveForward => "avanzar",
+            Action::MoveBack => "retroceder",
+            Action::StrafeLeft => "lateral_izquierda",
+            Action::StrafeRight => "lateral_derecha",
+            Action::TurnLeft => "girar_izquierda",
+            Action::TurnRight => "girar_derecha",
+            Action::Interact => "interactuar",
+            Action::Pause => "pausa",
+            Action::ToggleMap => "mapa",
+            Action::ToggleHint => "pista",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveForward => "Avanzar",
+            Action::MoveBack => "Retroceder",
+            Action::StrafeLeft => "Paso lateral izquierda",
+            Action::StrafeRight => "Paso lateral derecha",
+            Action::TurnLeft => "Girar izquierda",
+            Action::TurnRight => "Girar derecha",
+            Action::Interact => "Interactuar",
+            Action::Pause => "Pausa",
+            Action::ToggleMap => "Minimapa",
+            Action::ToggleHint => "Pista",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyboardKey),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButton),
+    // Medio eje del mando: `true` para la mitad positiva (derecha/abajo), `false` para la negativa
+    GamepadAxis(GamepadAxis, bool),
+}
+
+// Controles asignados a cada acción; una acción puede tener varios
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    actions: [Vec<Binding>; ACTION_COUNT],
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::{GamepadAxis as Axis, GamepadButton as Button, Key};
+
+        let mut bindings = Bindings { actions: Default::default() };
+        bindings.set(Action::MoveForward, vec![Key(KeyboardKey::KEY_W), Axis(GamepadAxis::GAMEPAD_AXIS_LEFT_Y, false)]);
+        bindings.set(Action::MoveBack, vec![Key(KeyboardKey::KEY_S), Axis(GamepadAxis::GAMEPAD_AXIS_LEFT_Y, true)]);
+        bindings.set(Action::StrafeLeft, vec![Key(KeyboardKey::KEY_A), Axis(GamepadAxis::GAMEPAD_AXIS_LEFT_X, false)]);
+        bindings.set(Action::StrafeRight, vec![Key(KeyboardKey::KEY_D), Axis(GamepadAxis::GAMEPAD_AXIS_LEFT_X, true)]);
+        bindings.set(Action::TurnLeft, vec![Key(KeyboardKey::KEY_LEFT), Axis(GamepadAxis::GAMEPAD_AXIS_RIGHT_X, false)]);
+        bindings.set(Action::TurnRight, vec![Key(KeyboardKey::KEY_RIGHT), Axis(GamepadAxis::GAMEPAD_AXIS_RIGHT_X, true)]);
+        bindings.set(Action::Interact, vec![Key(KeyboardKey::KEY_E), Button(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN)]);
+        bindings.set(Action::Pause, vec![Key(KeyboardKey::KEY_ESCAPE), Button(GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT)]);
+        bindings.set(Action::ToggleMap, vec![Key(KeyboardKey::KEY_M), Button(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP)]);
+        bindings.set(Action::ToggleHint, vec![Key(KeyboardKey::KEY_H), Button(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT)]);
+        bindings
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        &self.actions[action.index()]
+    }
+
+    pub fn set(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.actions[action.index()] = bindings;
+    }
+
+    // Primera tecla asignada; es la que se muestra y se cambia desde el menú de ajustes
+    pub fn key(&self, action: Action) -> Option<KeyboardKey> {
+        self.get(action).iter().find_map(|binding| match binding {
+            Binding::Key(key) => Some(*key),
+            _ => None,
+        })
+    }
+
+    // Sustituye la primera tecla de la acción y conserva los controles de ratón y mando
+    pub fn set_key(&mut self, action: Action, key: KeyboardKey) {
+        let bindings = &mut self.actions[action.index()];
+        match bindings.iter_mut().find(|binding| matches!(binding, Binding::Key(_))) {
+            Some(binding) => *binding = Binding::Key(key),
+            None => bindings.insert(0, Binding::Key(key)),
+        }
+    }
+}
+
+// Estado de las acciones en el fotograma actual; el juego solo lee esto
+#[derive(Default)]
+pub struct Input {
+    values: [f32; ACTION_COUNT],
+    // Lo que aportan solo los ejes del mando, para girar con el stick a su propia velocidad
+    stick_values: [f32; ACTION_COUNT],
+    down: [bool; ACTION_COUNT],
+    pressed: [bool; ACTION_COUNT],
+    // Giro en radianes pedido por el ratón en este fotograma
+    pub look: f32,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Lee teclado, ratón y mando. Con `mouse_look` el ratón se recentra en cada fotograma
+    // y su desplazamiento horizontal se convierte en giro.
+    pub fn update(&mut self, rl: &mut RaylibHandle, bindings: &Bindings, dead_zone: f32, mouse_look: Option<MouseLook>) {
+        let gamepad = rl.is_gamepad_available(GAMEPAD);
+        for action in Action::ALL {
+            let (mut value, mut stick_value) = (0.0_f32, 0.0_f32);
+            for &binding in bindings.get(action) {
+                let binding_value = binding_value(rl, binding, gamepad, dead_zone);
+                value = value.max(binding_value);
+                if matches!(binding, Binding::GamepadAxis(..)) {
+                    stick_value = stick_value.max(binding_value);
+                }
+            }
+            let index = action.index();
+            let down = value >= PRESS_THRESHOLD;
+            self.pressed[index] = down && !self.down[index];
+            self.down[index] = down;
+            self.values[index] = value;
+            self.stick_values[index] = stick_value;
+        }
+
+        self.look = 0.0;
+        if let Some(look) = mouse_look {
+            let center_x = look.screen_width / 2;
+            let delta_x = rl.get_mouse_x() - center_x;
+            let direction = if look.invert { -1.0 } else { 1.0 };
+            self.look = delta_x as f32 * look.sensitivity * direction;
+            rl.set_mouse_position((center_x as f32, rl.get_mouse_y() as f32));
+        }
+    }
+
+    // Intensidad de la acción entre 0 y 1 (las teclas valen 0 o 1, los ejes todo el recorrido)
+    pub fn value(&self, action: Action) -> f32 {
+        self.values[action.index()]
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed[action.index()]
+    }
+
+    // Eje formado por dos acciones opuestas, entre -1 y 1
+    pub fn axis(&self, negative: Action, positive: Action) -> f32 {
+        self.value(positive) - self.value(negative)
+    }
+
+    // Como `axis`, pero contando solo los ejes del mando
+    pub fn stick_axis(&self, negative: Action, positive: Action) -> f32 {
+        self.stick_values[positive.index()] - self.stick_values[negative.index()]
+    }
+}
+
+// Parámetros del giro con ratón durante la partida
+#[derive(Debug, Clone, Copy)]
+pub struct MouseLook {
+    pub screen_width: i32,
+    pub sensitivity: f32,
+    pub invert: bool,
+}
+
+fn binding_value(rl: &RaylibHandle, binding: Binding, gamepad: bool, dead_zone: f32) -> f32 {
+    let active = match binding {
+        Binding::Key(key) => rl.is_key_down(key),
+        Binding::Mouse(button) => rl.is_mouse_button_down(button),
+        Binding::GamepadButton(button) => gamepad && rl.is_gamepad_button_down(GAMEPAD, button),
+        Binding::GamepadAxis(axis, positive) => {
+            if !gamepad {
+                return 0.0;
+            }
+            let mut value = rl.get_gamepad_axis_movement(GAMEPAD, axis);
+            // Los gatillos van de -1 (suelto) a 1 (a fondo)
+            if matches!(axis, GamepadAxis::GAMEPAD_AXIS_LEFT_TRIGGER | GamepadAxis::GAMEPAD_AXIS_RIGHT_TRIGGER) {
+                value = (value + 1.0) / 2.0;
+            }
+            let value = if positive { value } else { -value };
+            return apply_dead_zone(value, dead_zone);
+        }
+    };
+    if active { 1.0 } else { 0.0 }
+}
+
+// Anula el recorrido dentro de la zona muerta y reescala el resto para que siga llegando a 1
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value <= dead_zone {
+        0.0
+    } else {
+        ((value - dead_zone) / (1.0 - dead_zone).max(f32::EPSILON)).min(1.0)
+    }
+}
+
+// Confirmar en pantallas y menús: ENTER, espacio o A/START en el mando
+pub fn confirm_pressed(rl: &RaylibHandle) -> bool {
+    rl.is_key_pressed(KeyboardKey::KEY_ENTER)
+        || rl.is_key_pressed(KeyboardKey::KEY_SPACE)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT)
+}
+
+// Volver en pantallas y menús: ESC o B en el mando
+pub fn back_pressed(rl: &RaylibHandle) -> bool {
+    rl.is_key_pressed(KeyboardKey::KEY_ESCAPE)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT)
+}
+
+// Navegación vertical en menús: -1 arriba, 1 abajo
+pub fn menu_direction(rl: &RaylibHandle) -> i32 {
+    let up = rl.is_key_pressed(KeyboardKey::KEY_UP)
+        || rl.is_key_pressed(KeyboardKey::KEY_W)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP);
+    let down = rl.is_key_pressed(KeyboardKey::KEY_DOWN)
+        || rl.is_key_pressed(KeyboardKey::KEY_S)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN);
+    down as i32 - up as i32
+}
+
+// Cambio de valor en menús: -1 izquierda, 1 derecha
+pub fn menu_adjust(rl: &RaylibHandle) -> i32 {
+    let left = rl.is_key_pressed(KeyboardKey::KEY_LEFT)
+        || rl.is_key_pressed(KeyboardKey::KEY_A)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT);
+    let right = rl.is_key_pressed(KeyboardKey::KEY_RIGHT)
+        || rl.is_key_pressed(KeyboardKey::KEY_D)
+        || rl.is_gamepad_button_pressed(GAMEPAD, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT);
+    right as i32 - left as i32
+}
+
+// Nombres de los controles en el archivo de ajustes
+const KEY_NAMES: [(KeyboardKey, &str); 46] = [
+    (KeyboardKey::KEY_A, "A"),
+    (KeyboardKey::KEY_B, "B"),
+    (KeyboardKey::KEY_C, "C"),
+    (KeyboardKey::KEY_D, "D"),
+    (KeyboardKey::KEY_E, "E"),
+    (KeyboardKey::KEY_F, "F"),
+    (KeyboardKey::KEY_G, "G"),
+    (KeyboardKey::KEY_H, "H"),
+    (KeyboardKey::KEY_I, "I"),
+    (KeyboardKey::KEY_J, "J"),
+    (KeyboardKey::KEY_K, "K"),
+    (KeyboardKey::KEY_L, "L"),
+    (KeyboardKey::KEY_M, "M"),
+    (KeyboardKey::KEY_N, "N"),
+    (KeyboardKey::KEY_O, "O"),
+    (KeyboardKey::KEY_P, "P"),
+    (KeyboardKey::KEY_Q, "Q"),
+    (KeyboardKey::KEY_R, "R"),
+    (KeyboardKey::KEY_S, "S"),
+    (KeyboardKey::KEY_T, "T"),
+    (KeyboardKey::KEY_U, "U"),
+    (KeyboardKey::KEY_V, "V"),
+    (KeyboardKey::KEY_W, "W"),
+    (KeyboardKey::KEY_X, "X"),
+    (KeyboardKey::KEY_Y, "Y"),
+    (KeyboardKey::KEY_Z, "Z"),
+    (KeyboardKey::KEY_ZERO, "0"),
+    (KeyboardKey::KEY_ONE, "1"),
+    (KeyboardKey::KEY_TWO, "2"),
+    (KeyboardKey::KEY_THREE, "3"),
+    (KeyboardKey::KEY_FOUR, "4"),
+    (KeyboardKey::KEY_FIVE, "5"),
+    (KeyboardKey::KEY_SIX, "6"),
+    (KeyboardKey::KEY_SEVEN, "7"),
+    (KeyboardKey::KEY_EIGHT, "8"),
+    (KeyboardKey::KEY_NINE, "9"),
+    (KeyboardKey::KEY_UP, "ARRIBA"),
+    (KeyboardKey::KEY_DOWN, "ABAJO"),
+    (KeyboardKey::KEY_LEFT, "IZQUIERDA"),
+    (KeyboardKey::KEY_RIGHT, "DERECHA"),
+    (KeyboardKey::KEY_SPACE, "ESPACIO"),
+    (KeyboardKey::KEY_TAB, "TAB"),
+    (KeyboardKey::KEY_LEFT_SHIFT, "MAYUS"),
+    (KeyboardKey::KEY_LEFT_CONTROL, "CTRL"),
+    (KeyboardKey::KEY_LEFT_ALT, "ALT"),
+    (KeyboardKey::KEY_ESCAPE, "ESC"),
+];
+
+const MOUSE_NAMES: [(MouseButton, &str); 3] = [
+    (MouseButton::MOUSE_BUTTON_LEFT, "RATON_IZQ"),
+    (MouseButton::MOUSE_BUTTON_RIGHT, "RATON_DER"),
+    (MouseButton::MOUSE_BUTTON_MIDDLE, "RATON_CENTRO"),
+];
+
+const GAMEPAD_BUTTON_NAMES: [(GamepadButton, &str); 12] = [
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN, "MANDO_A"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, "MANDO_B"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT, "MANDO_X"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP, "MANDO_Y"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP, "MANDO_ARRIBA"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN, "MANDO_ABAJO"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT, "MANDO_IZQUIERDA"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT, "MANDO_DERECHA"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1, "MANDO_LB"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1, "MANDO_RB"),
+    (GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT, "MANDO_SELECT"),
+    (GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT, "MANDO_START"),
+];
+
+// Los ejes se escriben con el signo de la mitad que activa la acción (`STICK_IZQ_Y-`)
+const GAMEPAD_AXIS_NAMES: [(GamepadAxis, &str); 6] = [
+    (GamepadAxis::GAMEPAD_AXIS_LEFT_X, "STICK_IZQ_X"),
+    (GamepadAxis::GAMEPAD_AXIS_LEFT_Y, "STICK_IZQ_Y"),
+    (GamepadAxis::GAMEPAD_AXIS_RIGHT_X, "STICK_DER_X"),
+    (GamepadAxis::GAMEPAD_AXIS_RIGHT_Y, "STICK_DER_Y"),
+    (GamepadAxis::GAMEPAD_AXIS_LEFT_TRIGGER, "GATILLO_IZQ"),
+    (GamepadAxis::GAMEPAD_AXIS_RIGHT_TRIGGER, "GATILLO_DER"),
+];
+
+pub fn key_name(key: KeyboardKey) -> &'static str {
+    lookup_name(&KEY_NAMES, key)
+}
+
+pub fn binding_name(binding: Binding) -> String {
+    match binding {
+        Binding::Key(key) => key_name(key).to_string(),
+        Binding::Mouse(button) => lookup_name(&MOUSE_NAMES, button).to_string(),
+        Binding::GamepadButton(button) => lookup_name(&GAMEPAD_BUTTON_NAMES, button).to_string(),
+        Binding::GamepadAxis(axis, positive) => {
+            format!("{}{}", lookup_name(&GAMEPAD_AXIS_NAMES, axis), if positive { '+' } else { '-' })
+        }
+    }
+}
+
+pub fn binding_from_name(name: &str) -> Option<Binding> {
+    let name = name.trim().to_uppercase();
+    if let Some(axis_name) = name.strip_suffix('+').or_else(|| name.strip_suffix('-')) {
+        let positive = name.ends_with('+');
+        return lookup_value(&GAMEPAD_AXIS_NAMES, axis_name).map(|axis| Binding::GamepadAxis(axis, positive));
+    }
+    lookup_value(&KEY_NAMES, &name)
+        .map(Binding::Key)
+        .or_else(|| lookup_value(&MOUSE_NAMES, &name).map(Binding::Mouse))
+        .or_else(|| lookup_value(&GAMEPAD_BUTTON_NAMES, &name).map(Binding::GamepadButton))
+}
+
+fn lookup_name<T: PartialEq + Copy>(table: &[(T, &'static str)], value: T) -> &'static str {
+    table.iter().find(|(v, _)| *v == value).map(|(_, name)| *name).unwrap_or("?")
+}
+
+fn lookup_value<T: Copy>(table: &[(T, &str)], name: &str) -> Option<T> {
+    table.iter().find(|(_, n)| *n == name).map(|(value, _)| *value)
+}
+
+// Tecla asignable pulsada en este fotograma (para reasignar controles desde el menú).
+// ESC queda fuera porque cancela la reasignación.
+pub fn assignable_key(rl: &RaylibHandle) -> Option<KeyboardKey> {
+    KEY_NAMES
+        .iter()
+        .map(|(key, _)| *key)
+        .filter(|&key| key != KeyboardKey::KEY_ESCAPE)
+        .find(|&key| rl.is_key_pressed(key))
+}
@@ -1,34 +1,520 @@
 use raylib::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use crate::damage::DamagePolicy;
+use crate::doors::{is_door_char, Door, Key, KeyColor};
+use crate::pickups::{Pickup, PickupKind};
+use crate::teleporters::{Teleporter, PORTAL_CHAR, TELEPORTER_CHAR};
+use crate::validator::{validate, Issue};
 
-pub const MAZE: &[&str] = &[
-    "AAAAAAAAAAAA",
-    "A     A    A",
-    "A BBB A CC A",
-    "A B   A C  A",
-    "A B BBB CCCC",
-    "A   A      A",
-    "BBB A DDDD A",
-    "A   A    A A",
-    "A DDDDD CC A",
-    "A       A  A",
-    "AAAAAAAAAAEA",
-    "AAAAAAAAAAAA",
-];
-
-pub fn get_maze_cell(x: f32, y: f32, block_size: i32) -> char {
-    let col = (x / block_size as f32) as usize;
-    let row = (y / block_size as f32) as usize;
-    if row >= MAZE.len() || col >= MAZE[0].len() {
-        return '#';
-    }
-    MAZE[row].chars().nth(col).unwrap_or('#')
-}
-
-pub fn is_wall(x: f32, y: f32, block_size: i32) -> bool {
-    let cell = get_maze_cell(x, y, block_size);
+// Formato de nivel:
+//
+//   # comentario
+//   nombre = Laberinto clásico
+//   inicio = 1 1        (fila columna)
+//   angulo = 0          (grados)
+//   salida = 10 10      (fila columna, opcional)
+//   decoracion = 3 5 antorcha   (fila columna tipo, se puede repetir)
+//   danio = peligro     (vidas, salud, peligro o zen; opcional)
+//   peligro = C D       (paredes que hacen daño con `danio = peligro`, opcional)
+//   llave = 5 3 roja    (fila columna color: roja, verde o azul; se puede repetir)
+//   enemigo = 3 5, 3 10, 8 10   (ruta de patrulla, empieza en la primera celda; se puede repetir)
+//   objeto = 4 7 moneda (fila columna tipo: moneda, corazon, escudo, velocidad o mapa; se puede repetir)
+//   teleporte = 2 3 -> 8 10 90     (celda `T` de origen -> destino y ángulo de salida; se puede repetir)
+//   portal = 2 3 0 -> 8 10 90      (celda `O` y ángulo de entrada -> destino y ángulo de salida)
+//   altura = 4 2 0.3               (fila columna altura: de la pared en un muro, del suelo en el resto)
+//   altura = 6 2 8 5 -0.5          (lo mismo para el rectángulo de (6, 2) a (8, 5))
+//   ---
+//   AAAAAAAAAAAA
+//   A     A    A
+//   ...
+//
+// Todo lo que está antes de `---` es cabecera; lo que sigue es la cuadrícula. En ella `P` es
+// una puerta, `1`, `2` y `3` puertas cerradas con la llave roja, verde y azul, `T` un
+// teletransporte y `O` un portal.
+pub const DEFAULT_LEVEL: &str = "assets/levels/clasico.txt";
+
+// Caracteres válidos en la cuadrícula
+pub const CELL_CHARS: &[char] = &[' ', '#', 'A', 'B', 'C', 'D', 'E', 'P', '1', '2', '3', 'T', 'O'];
+
+// Alturas en bloques. El suelo está a 0 y las paredes llegan al techo, salvo que el nivel diga
+// otra cosa; los ojos van a `EYE_HEIGHT` sobre el suelo que se pisa, así que el suelo más alto
+// deja un poco de espacio bajo el techo
+pub const CEILING_HEIGHT: f32 = 1.0;
+pub const EYE_HEIGHT: f32 = 0.5;
+pub const MIN_FLOOR_HEIGHT: f32 = -0.5;
+pub const MAX_FLOOR_HEIGHT: f32 = 0.4;
+
+// Tipos de decoración que se dibujan como sprites
+pub const DECORATIONS: &[&str] = &["antorcha", "columna", "planta", "barril"];
+
+pub struct Decoration {
+    pub row: usize,
+    pub col: usize,
+    pub kind: String,
+}
+
+pub struct Maze {
+    pub name: String,
+    pub grid: Vec<Vec<char>>,
+    pub start: (usize, usize),
+    pub start_angle: f32,
+    pub exit: Option<(usize, usize)>,
+    pub decorations: Vec<Decoration>,
+    // Política de daño propia del nivel; los ajustes pueden imponer otra
+    pub damage: Option<DamagePolicy>,
+    pub hazards: Vec<char>,
+    // Estado de las puertas de la cuadrícula, en orden de lectura
+    pub doors: Vec<Door>,
+    pub keys: Vec<Key>,
+    // Ruta de patrulla de cada enemigo
+    pub enemies: Vec<Vec<(usize, usize)>>,
+    pub pickups: Vec<Pickup>,
+    pub teleporters: Vec<Teleporter>,
+    // Alturas que no son las de por defecto (ver `default_height`)
+    pub heights: HashMap<(usize, usize), f32>,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "línea {}, columna {}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum MazeError {
+    Io(String, std::io::Error),
+    Parse(String, ParseError),
+    Invalid(String, Vec<Issue>),
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeError::Io(path, err) => write!(f, "{}: {}", path, err),
+            MazeError::Parse(path, err) => write!(f, "{}: {}", path, err),
+            MazeError::Invalid(path, issues) => {
+                write!(f, "{}: {} problema(s)", path, issues.len())?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Maze {
+    pub fn load(path: &str) -> Result<Maze, MazeError> {
+        let text = fs::read_to_string(path).map_err(|err| MazeError::Io(path.to_string(), err))?;
+        let maze = Maze::parse(&text).map_err(|err| MazeError::Parse(path.to_string(), err))?;
+        let issues = validate(&maze);
+        if !issues.is_empty() {
+            return Err(MazeError::Invalid(path.to_string(), issues));
+        }
+        Ok(maze)
+    }
+
+    pub fn parse(text: &str) -> Result<Maze, ParseError> {
+        let mut name = String::from("Sin nombre");
+        let mut start = None;
+        let mut start_angle = 0.0f32;
+        let mut exit = None;
+        let mut decorations = Vec::new();
+        let mut damage = None;
+        let mut hazards = Vec::new();
+        let mut keys = Vec::new();
+        let mut enemies = Vec::new();
+        let mut pickups = Vec::new();
+        // Con la línea de cada uno, para comprobar su celda de origen al leer la cuadrícula
+        let mut teleporters: Vec<(Teleporter, usize)> = Vec::new();
+        // Rectángulo, altura y línea; se comprueban con la cuadrícula ya leída
+        let mut heights = Vec::new();
+        let mut grid: Vec<Vec<char>> = Vec::new();
+        let mut in_grid = false;
+        // Guardamos dónde se declaró cada posición para poder señalarla en los errores
+        let mut start_line = 0;
+        let mut exit_line = 0;
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim_end_matches('\r');
+
+            if in_grid {
+                grid.push(line.chars().collect());
+                continue;
+            }
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed == "---" {
+                in_grid = true;
+                continue;
+            }
+
+            let Some(eq) = line.find('=') else {
+                return Err(ParseError {
+                    line: line_number,
+                    column: line.len() - line.trim_start().len() + 1,
+                    message: "se esperaba `clave = valor` o `---`".to_string(),
+                });
+            };
+            let key = line[..eq].trim();
+            let value = line[eq + 1..].trim();
+            let value_column = eq + 2 + (line[eq + 1..].len() - line[eq + 1..].trim_start().len());
+
+            match key {
+                "nombre" => name = value.to_string(),
+                "inicio" => {
+                    start = Some(parse_cell(value, line_number, value_column)?);
+                    start_line = line_number;
+                }
+                "salida" => {
+                    exit = Some(parse_cell(value, line_number, value_column)?);
+                    exit_line = line_number;
+                }
+                "decoracion" => {
+                    let (position, kind) = value.rsplit_once(char::is_whitespace).unwrap_or((value, ""));
+                    let (row, col) = parse_cell(position.trim(), line_number, value_column)?;
+                    if !DECORATIONS.contains(&kind) {
+                        return Err(ParseError {
+                            line: line_number,
+                            column: value_column + value.rfind(kind).unwrap_or(0),
+                            message: format!("tipo de decoración desconocido `{}`", kind),
+                        });
+                    }
+                    decorations.push(Decoration { row, col, kind: kind.to_string() });
+                }
+                "danio" => {
+                    damage = Some(DamagePolicy::from_name(value).ok_or_else(|| ParseError {
+                        line: line_number,
+                        column: value_column,
+                        message: format!("política de daño desconocida `{}`", value),
+                    })?);
+                }
+                "peligro" => {
+                    for (offset, cell) in value.char_indices().filter(|(_, c)| !c.is_whitespace()) {
+                        if !is_wall_char(cell) {
+                            return Err(ParseError {
+                                line: line_number,
+                                column: value_column + offset,
+                                message: format!("`{}` no es un tipo de pared", cell),
+                            });
+                        }
+                        hazards.push(cell);
+                    }
+                }
+                "llave" => {
+                    let (position, color) = value.rsplit_once(char::is_whitespace).unwrap_or((value, ""));
+                    let (row, col) = parse_cell(position.trim(), line_number, value_column)?;
+                    let color = KeyColor::from_name(color).ok_or_else(|| ParseError {
+                        line: line_number,
+                        column: value_column + value.rfind(color).unwrap_or(0),
+                        message: format!("color de llave desconocido `{}`", color),
+                    })?;
+                    keys.push(Key { row, col, color });
+                }
+                "enemigo" => {
+                    let mut patrol = Vec::new();
+                    let mut offset = 0;
+                    for cell in value.split(',') {
+                        let leading = cell.len() - cell.trim_start().len();
+                        patrol.push(parse_cell(cell.trim(), line_number, value_column + offset + leading)?);
+                        offset += cell.len() + 1;
+                    }
+                    enemies.push(patrol);
+                }
+                "objeto" => {
+                    let (position, kind) = value.rsplit_once(char::is_whitespace).unwrap_or((value, ""));
+                    let (row, col) = parse_cell(position.trim(), line_number, value_column)?;
+                    let kind = PickupKind::from_name(kind).ok_or_else(|| ParseError {
+                        line: line_number,
+                        column: value_column + value.rfind(kind).unwrap_or(0),
+                        message: format!("tipo de objeto desconocido `{}`", kind),
+                    })?;
+                    pickups.push(Pickup { row, col, kind });
+                }
+                "teleporte" | "portal" => {
+                    let teleporter = parse_teleporter(value, key == "portal", line_number, value_column)?;
+                    if teleporters.iter().any(|(other, _)| other.from == teleporter.from) {
+                        return Err(ParseError {
+                            line: line_number,
+                            column: value_column,
+                            message: format!("ya hay un teletransporte en ({}, {})", teleporter.from.0, teleporter.from.1),
+                        });
+                    }
+                    teleporters.push((teleporter, line_number));
+                }
+                "altura" => heights.push((parse_height(value, line_number, value_column)?, line_number)),
+                "angulo" => start_angle = parse_angle(value, line_number, value_column)?,
+                _ => {
+                    return Err(ParseError {
+                        line: line_number,
+                        column: line.len() - line.trim_start().len() + 1,
+                        message: format!("clave desconocida `{}`", key),
+                    });
+                }
+            }
+        }
+
+        // Las líneas vacías al final del archivo no forman parte de la cuadrícula
+        while grid.last().is_some_and(|row| row.is_empty()) {
+            grid.pop();
+        }
+
+        if grid.is_empty() {
+            return Err(ParseError {
+                line: text.lines().count() + 1,
+                column: 1,
+                message: "el nivel no tiene cuadrícula (falta `---`)".to_string(),
+            });
+        }
+
+        let mut maze = Maze {
+            name,
+            grid,
+            start: (0, 0),
+            start_angle,
+            exit,
+            decorations,
+            damage,
+            hazards,
+            doors: Vec::new(),
+            keys,
+            enemies,
+            pickups,
+            teleporters: Vec::new(),
+            heights: HashMap::new(),
+        };
+        maze.doors = find_doors(&maze.grid);
+
+        for (((first_row, first_col), (last_row, last_col), height), line) in heights {
+            for row in first_row.min(last_row)..=first_row.max(last_row) {
+                for col in first_col.min(last_col)..=first_col.max(last_col) {
+                    let cell = maze.grid.get(row).and_then(|line| line.get(col)).copied();
+                    // Las paredes no pasan del techo y el suelo deja sitio para los ojos
+                    let valid = match cell {
+                        Some(cell) if is_wall_char(cell) => height > 0.0 && height <= CEILING_HEIGHT,
+                        Some(' ' | TELEPORTER_CHAR | PORTAL_CHAR) => (MIN_FLOOR_HEIGHT..=MAX_FLOOR_HEIGHT).contains(&height),
+                        Some(cell) => {
+                            return Err(ParseError {
+                                line,
+                                column: 1,
+                                message: format!("la celda ({}, {}) es `{}` y su altura no se puede cambiar", row, col, cell),
+                            });
+                        }
+                        None => {
+                            return Err(ParseError {
+                                line,
+                                column: 1,
+                                message: format!("la celda ({}, {}) está fuera del mapa", row, col),
+                            });
+                        }
+                    };
+                    if !valid {
+                        let range = if is_wall_char(maze.cell(row, col)) {
+                            format!("en un muro tiene que ser mayor que 0 y como mucho {}", CEILING_HEIGHT)
+                        } else {
+                            format!("en el suelo tiene que estar entre {} y {}", MIN_FLOOR_HEIGHT, MAX_FLOOR_HEIGHT)
+                        };
+                        return Err(ParseError {
+                            line,
+                            column: 1,
+                            message: format!("altura {} fuera de rango en ({}, {}): {}", height, row, col, range),
+                        });
+                    }
+                    maze.heights.insert((row, col), height);
+                }
+            }
+        }
+
+        for (teleporter, line) in teleporters {
+            let (row, col) = teleporter.from;
+            let expected = if teleporter.is_portal() { PORTAL_CHAR } else { TELEPORTER_CHAR };
+            if maze.cell(row, col) != expected {
+                return Err(ParseError {
+                    line,
+                    column: 1,
+                    message: format!("la celda de origen ({}, {}) no contiene `{}`", row, col, expected),
+                });
+            }
+            maze.teleporters.push(teleporter);
+        }
+
+        maze.start = match start {
+            Some((row, col)) => {
+                if maze.is_wall_cell(row, col) {
+                    return Err(ParseError {
+                        line: start_line,
+                        column: 1,
+                        message: format!("la celda de inicio ({}, {}) no es transitable", row, col),
+                    });
+                }
+                (row, col)
+            }
+            None => maze.find_cell(' ').unwrap_or((1, 1)),
+        };
+
+        match exit {
+            Some((row, col)) => {
+                if maze.cell(row, col) != 'E' {
+                    return Err(ParseError {
+                        line: exit_line,
+                        column: 1,
+                        message: format!("la celda de salida ({}, {}) no contiene `E`", row, col),
+                    });
+                }
+            }
+            None => maze.exit = maze.find_cell('E'),
+        }
+
+        Ok(maze)
+    }
+
+    // Serializa el laberinto en el mismo formato que lee `parse`
+    pub fn to_level_string(&self) -> String {
+        let mut text = String::new();
+        text.push_str(&format!("nombre = {}\n", self.name));
+        text.push_str(&format!("inicio = {} {}\n", self.start.0, self.start.1));
+        text.push_str(&format!("angulo = {}\n", self.start_angle.to_degrees().round()));
+        if let Some((row, col)) = self.exit {
+            text.push_str(&format!("salida = {} {}\n", row, col));
+        }
+        for decoration in &self.decorations {
+            text.push_str(&format!("decoracion = {} {} {}\n", decoration.row, decoration.col, decoration.kind));
+        }
+        if let Some(damage) = self.damage {
+            text.push_str(&format!("danio = {}\n", damage.name()));
+        }
+        if !self.hazards.is_empty() {
+            let hazards: Vec<String> = self.hazards.iter().map(|cell| cell.to_string()).collect();
+            text.push_str(&format!("peligro = {}\n", hazards.join(" ")));
+        }
+        for key in &self.keys {
+            text.push_str(&format!("llave = {} {} {}\n", key.row, key.col, key.color.name()));
+        }
+        for patrol in &self.enemies {
+            let cells: Vec<String> = patrol.iter().map(|(row, col)| format!("{} {}", row, col)).collect();
+            text.push_str(&format!("enemigo = {}\n", cells.join(", ")));
+        }
+        for pickup in &self.pickups {
+            text.push_str(&format!("objeto = {} {} {}\n", pickup.row, pickup.col, pickup.kind.name()));
+        }
+        for teleporter in &self.teleporters {
+            let ((from_row, from_col), (to_row, to_col)) = (teleporter.from, teleporter.to);
+            let exit = teleporter.exit_angle.to_degrees().round();
+            match teleporter.entry_angle {
+                Some(entry) => text.push_str(&format!("portal = {} {} {} -> {} {} {}\n", from_row, from_col, entry.to_degrees().round(), to_row, to_col, exit)),
+                None => text.push_str(&format!("teleporte = {} {} -> {} {} {}\n", from_row, from_col, to_row, to_col, exit)),
+            }
+        }
+        let mut heights: Vec<(&(usize, usize), &f32)> = self.heights.iter().collect();
+        heights.sort_by_key(|(cell, _)| **cell);
+        for ((row, col), height) in heights {
+            text.push_str(&format!("altura = {} {} {}\n", row, col, height));
+        }
+        text.push_str("---\n");
+        for line in &self.grid {
+            text.extend(line.iter());
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.len()
+    }
+
+    // Celda por fila/columna; fuera del mapa se considera muro
+    pub fn cell(&self, row: usize, col: usize) -> char {
+        self.grid
+            .get(row)
+            .and_then(|line| line.get(col))
+            .copied()
+            .unwrap_or('#')
+    }
+
+    // Muro o puerta que no está abierta del todo
+    pub fn is_wall_cell(&self, row: usize, col: usize) -> bool {
+        let cell = self.cell(row, col);
+        is_wall_char(cell) || (is_door_char(cell) && self.door(row, col).is_none_or(Door::is_blocking))
+    }
+
+    // Altura de la pared o del suelo de la celda; fuera del mapa, la de un muro
+    pub fn cell_height(&self, row: usize, col: usize) -> f32 {
+        self.heights.get(&(row, col)).copied().unwrap_or_else(|| default_height(self.cell(row, col)))
+    }
+
+    pub fn teleporter(&self, row: usize, col: usize) -> Option<&Teleporter> {
+        self.teleporters.iter().find(|teleporter| teleporter.from == (row, col))
+    }
+
+    pub fn door(&self, row: usize, col: usize) -> Option<&Door> {
+        self.doors.iter().find(|door| door.row == row && door.col == col)
+    }
+
+    pub fn door_mut(&mut self, row: usize, col: usize) -> Option<&mut Door> {
+        self.doors.iter_mut().find(|door| door.row == row && door.col == col)
+    }
+
+    // Avanza las puertas que se están abriendo o cerrando
+    pub fn update_doors(&mut self, dt: f32) {
+        for door in &mut self.doors {
+            door.update(dt);
+        }
+    }
+
+    // Todas las puertas cerradas, como al empezar el nivel
+    pub fn close_doors(&mut self) {
+        for door in &mut self.doors {
+            door.set_open(false);
+        }
+    }
+
+    pub fn get_maze_cell(&self, x: f32, y: f32, block_size: i32) -> char {
+        if x < 0.0 || y < 0.0 {
+            return '#';
+        }
+        let col = (x / block_size as f32) as usize;
+        let row = (y / block_size as f32) as usize;
+        self.cell(row, col)
+    }
+
+    pub fn find_cell(&self, cell: char) -> Option<(usize, usize)> {
+        for (row, line) in self.grid.iter().enumerate() {
+            for (col, c) in line.iter().enumerate() {
+                if *c == cell {
+                    return Some((row, col));
+                }
+            }
+        }
+        None
+    }
+}
+
+pub fn is_wall_char(cell: char) -> bool {
     cell == '#' || cell == 'A' || cell == 'B' || cell == 'C' || cell == 'D'
 }
 
+// Los muros y la salida llegan al techo; el resto es suelo a 0
+fn default_height(cell: char) -> f32 {
+    if is_wall_char(cell) || cell == 'E' { CEILING_HEIGHT } else { 0.0 }
+}
+
 pub fn wall_color(cell: char) -> Color {
     match cell {
         '#' => Color::new(180, 60, 60, 255),    // Rojo ladrillo
@@ -37,17 +523,255 @@ pub fn wall_color(cell: char) -> Color {
         'C' => Color::new(180, 180, 60, 255),   // Amarillo
         'D' => Color::new(180, 60, 180, 255),   // Magenta
         'E' => Color::new(255, 99, 130, 255),   // Rosa (meta)
-        _ => Color::new(120, 120, 120, 255),    // Gris
+        'P' => Color::new(140, 95, 50, 255),    // Madera (puerta)
+        'T' => Color::new(150, 90, 230, 255),   // Violeta (teletransporte)
+        'O' => Color::new(60, 210, 230, 255),   // Cian (portal)
+        _ => match KeyColor::from_door_char(cell) {
+            Some(color) => color.color(),       // Puerta con llave, del color de la llave
+            None => Color::new(120, 120, 120, 255), // Gris
+        },
     }
 }
 
-pub fn find_cell(cell: char) -> Option<(usize, usize)> {
-    for (row, line) in MAZE.iter().enumerate() {
-        for (col, c) in line.chars().enumerate() {
-            if c == cell {
-                return Some((row, col));
+// Una puerta por cada celda de puerta, orientada según los muros que la rodean
+fn find_doors(grid: &[Vec<char>]) -> Vec<Door> {
+    let cell = |row: Option<usize>, col: usize| {
+        row.and_then(|row| grid.get(row)).and_then(|line| line.get(col)).copied().unwrap_or('#')
+    };
+    let mut doors = Vec::new();
+    for (row, line) in grid.iter().enumerate() {
+        for (col, &c) in line.iter().enumerate() {
+            if !is_door_char(c) {
+                continue;
             }
+            doors.push(Door {
+                row,
+                col,
+                lock: KeyColor::from_door_char(c),
+                vertical: is_wall_char(cell(row.checked_sub(1), col)) && is_wall_char(cell(Some(row + 1), col)),
+                open: 0.0,
+                opening: false,
+            });
         }
     }
-    None
-}
\ No newline at end of file
+    doors
+}
+
+// `fila columna -> fila columna angulo`; los portales llevan también el ángulo de entrada
+// (`fila columna angulo -> ...`)
+fn parse_teleporter(value: &str, portal: bool, line: usize, column: usize) -> Result<Teleporter, ParseError> {
+    let Some((from, to)) = value.split_once("->") else {
+        return Err(ParseError {
+            line,
+            column,
+            message: format!("se esperaba `origen -> destino angulo`, se encontró `{}`", value),
+        });
+    };
+    let to_column = column + from.len() + 2 + (to.len() - to.trim_start().len());
+    let (from, entry_angle) = if portal {
+        let (cell, angle) = from.trim().rsplit_once(char::is_whitespace).unwrap_or((from.trim(), ""));
+        (cell, Some(parse_angle(angle, line, column + from.trim_end().len() - angle.len())?))
+    } else {
+        (from.trim(), None)
+    };
+    let (to, exit_angle) = to.trim().rsplit_once(char::is_whitespace).unwrap_or((to.trim(), ""));
+    Ok(Teleporter {
+        from: parse_cell(from, line, column)?,
+        to: parse_cell(to.trim(), line, to_column)?,
+        exit_angle: parse_angle(exit_angle, line, to_column + to.len() + 1)?,
+        entry_angle,
+    })
+}
+
+fn parse_angle(value: &str, line: usize, column: usize) -> Result<f32, ParseError> {
+    let degrees: f32 = value.parse().map_err(|_| ParseError {
+        line,
+        column,
+        message: format!("ángulo inválido `{}`", value),
+    })?;
+    Ok(degrees.to_radians())
+}
+
+// Primera y última celda de un rectángulo y su altura
+type HeightArea = ((usize, usize), (usize, usize), f32);
+
+// `fila columna altura` o `fila columna fila columna altura` para un rectángulo
+fn parse_height(value: &str, line: usize, column: usize) -> Result<HeightArea, ParseError> {
+    let (cells, height) = value.rsplit_once(char::is_whitespace).unwrap_or(("", value));
+    let height: f32 = height.parse().map_err(|_| ParseError {
+        line,
+        column: column + value.rfind(height).unwrap_or(0),
+        message: format!("altura inválida `{}`", height),
+    })?;
+    let numbers: Vec<&str> = cells.split_whitespace().collect();
+    let (first, last) = match numbers.len() {
+        2 => {
+            let cell = parse_cell(cells.trim(), line, column)?;
+            (cell, cell)
+        }
+        4 => {
+            // Donde empieza el tercer número, para señalar bien los errores de la segunda celda
+            let split = cells
+                .char_indices()
+                .filter(|&(index, c)| !c.is_whitespace() && cells[..index].ends_with(char::is_whitespace))
+                .nth(1)
+                .map_or(0, |(index, _)| index);
+            (parse_cell(cells[..split].trim(), line, column)?, parse_cell(cells[split..].trim(), line, column + split)?)
+        }
+        _ => {
+            return Err(ParseError {
+                line,
+                column,
+                message: format!("se esperaba `fila columna altura` o `fila columna fila columna altura`, se encontró `{}`", value),
+            });
+        }
+    };
+    Ok((first, last, height))
+}
+
+// Lee una posición `fila columna`
+fn parse_cell(value: &str, line: usize, column: usize) -> Result<(usize, usize), ParseError> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() != 2 {
+        return Err(ParseError {
+            line,
+            column,
+            message: format!("se esperaba `fila columna`, se encontró `{}`", value),
+        });
+    }
+    let row = parts[0].parse().map_err(|_| ParseError {
+        line,
+        column,
+        message: format!("fila inválida `{}`", parts[0]),
+    })?;
+    let col = parts[1].parse().map_err(|_| ParseError {
+        line,
+        column: column + value.rfind(parts[1]).unwrap_or(0),
+        message: format!("columna inválida `{}`", parts[1]),
+    })?;
+    Ok((row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::IssueKind;
+
+    // Todo lo que sabe escribir `to_level_string`: puerta con llave, enemigo, objeto, teletransporte,
+    // portal y alturas de muro y de suelo
+    const FULL_LEVEL: &str = "\
+nombre = Prueba
+inicio = 1 1
+angulo = 90
+salida = 3 6
+decoracion = 1 4 barril
+danio = peligro
+peligro = C D
+llave = 1 2 roja
+enemigo = 3 2, 3 4
+objeto = 1 3 moneda
+teleporte = 2 5 -> 1 5 180
+portal = 2 1 90 -> 3 1 270
+altura = 0 0 0.5
+altura = 1 1 1 2 0.2
+---
+#######
+#     #
+#O#1#T#
+#     E
+#######
+";
+
+    fn parse_error(text: &str) -> ParseError {
+        match Maze::parse(text) {
+            Ok(_) => panic!("se esperaba un error al leer:\n{}", text),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn unknown_keys_point_at_the_key() {
+        let err = parse_error("nombre = x\n  color = rojo\n---\n###\n");
+        assert_eq!((err.line, err.column), (2, 3));
+        assert!(err.message.contains("color"));
+    }
+
+    #[test]
+    fn lines_without_equals_sign_point_at_their_first_character() {
+        let err = parse_error("# comentario\n\n   hola\n---\n###\n");
+        assert_eq!((err.line, err.column), (3, 4));
+    }
+
+    #[test]
+    fn bad_cells_point_at_the_wrong_number() {
+        let err = parse_error("inicio = x 1\n---\n###\n");
+        assert_eq!((err.line, err.column), (1, 10));
+        let err = parse_error("inicio = 1 x\n---\n###\n");
+        assert_eq!((err.line, err.column), (1, 12));
+        let err = parse_error("nombre = a\nsalida =  1\n---\n###\n");
+        assert_eq!((err.line, err.column), (2, 11));
+    }
+
+    #[test]
+    fn bad_cells_in_enemy_routes_point_inside_the_list() {
+        // La tercera celda empieza en la columna 22 y su columna inválida está en la 24
+        let err = parse_error("enemigo = 1 1, 2 2,  3 x\n---\n###\n");
+        assert_eq!((err.line, err.column), (1, 24));
+        let err = parse_error("enemigo = 1 1, 2\n---\n###\n");
+        assert_eq!((err.line, err.column), (1, 16));
+    }
+
+    #[test]
+    fn unknown_names_point_at_the_name() {
+        let err = parse_error("decoracion = 1 1 farola\n---\n###\n");
+        assert_eq!((err.line, err.column), (1, 18));
+        let err = parse_error("llave = 2 3 morada\n---\n###\n");
+        assert_eq!((err.line, err.column), (1, 13));
+    }
+
+    #[test]
+    fn missing_grid_points_past_the_last_line() {
+        let err = parse_error("nombre = x\ninicio = 1 1\n");
+        assert_eq!((err.line, err.column), (3, 1));
+    }
+
+    #[test]
+    fn non_rectangular_grids_are_reported_where_the_row_ends() {
+        // El parser acepta filas de distinto largo; es la validación la que las señala
+        let maze = Maze::parse("---\n#####\n#  #\n#   ##\n#####\n").unwrap();
+        let issues: Vec<Issue> = validate(&maze)
+            .into_iter()
+            .filter(|issue| matches!(issue.kind, IssueKind::UnequalRowLength { .. }))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                Issue { row: 1, col: 4, kind: IssueKind::UnequalRowLength { expected: 5, found: 4 } },
+                Issue { row: 2, col: 5, kind: IssueKind::UnequalRowLength { expected: 5, found: 6 } },
+            ]
+        );
+    }
+
+    #[test]
+    fn level_string_round_trips() {
+        let maze = Maze::parse(FULL_LEVEL).unwrap();
+        assert!(validate(&maze).is_empty(), "{:?}", validate(&maze));
+        let text = maze.to_level_string();
+        let again = Maze::parse(&text).unwrap();
+        assert_eq!(again.to_level_string(), text);
+
+        assert_eq!(again.grid, maze.grid);
+        assert_eq!((again.start, again.exit), (maze.start, maze.exit));
+        assert_eq!(again.start_angle, maze.start_angle);
+        assert_eq!(again.damage, maze.damage);
+        assert_eq!(again.hazards, maze.hazards);
+        assert_eq!(again.enemies, maze.enemies);
+        assert_eq!(again.pickups, maze.pickups);
+        assert_eq!(again.teleporters, maze.teleporters);
+        assert_eq!(again.heights, maze.heights);
+        assert_eq!(again.doors.len(), 1);
+        assert_eq!(again.keys.len(), 1);
+        assert_eq!(again.cell_height(1, 2), 0.2);
+        assert_eq!(again.cell_height(0, 0), 0.5);
+    }
+}
@@ -0,0 +1,295 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use crate::maze::{is_wall_char, Maze};
+
+// Celda como (fila, columna), igual que `Maze::find_cell`
+pub type Cell = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Bfs,
+    Dijkstra,
+    AStar,
+}
+
+// Cómo cuentan las puertas al buscar un camino
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Doors {
+    // Como pasillo, aunque estén cerradas: el jugador las puede abrir
+    Passable,
+    // Solo las que están abiertas del todo; los enemigos no abren puertas
+    OpenOnly,
+}
+
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub cells: Vec<Cell>,
+    pub distance: f32,
+}
+
+// Nodo de la cola de prioridad ordenado de menor a mayor coste
+#[derive(PartialEq)]
+struct Node {
+    priority: f32,
+    cell: Cell,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub fn cell_at(x: f32, y: f32, block_size: i32) -> Cell {
+    ((y.max(0.0) / block_size as f32) as usize, (x.max(0.0) / block_size as f32) as usize)
+}
+
+// Las puertas cuentan como transitables aunque estén cerradas: se pueden abrir
+pub fn is_passable(maze: &Maze, cell: Cell) -> bool {
+    cell.0 < maze.height() && cell.1 < maze.width() && !is_wall_char(maze.cell(cell.0, cell.1))
+}
+
+fn is_passable_with(maze: &Maze, cell: Cell, doors: Doors) -> bool {
+    is_passable(maze, cell) && (doors == Doors::Passable || !maze.is_wall_cell(cell.0, cell.1))
+}
+
+// Vecinos transitables en las cuatro direcciones. Un teletransporte no se puede
+// cruzar andando: pisarlo siempre lleva a su destino, así que es su único vecino
+pub fn neighbors(maze: &Maze, cell: Cell) -> Vec<Cell> {
+    neighbors_with(maze, cell, Doors::Passable)
+}
+
+fn neighbors_with(maze: &Maze, (row, col): Cell, doors: Doors) -> Vec<Cell> {
+    if let Some(teleporter) = maze.teleporter(row, col) {
+        return if is_passable_with(maze, teleporter.to, doors) { vec![teleporter.to] } else { Vec::new() };
+    }
+    let mut result = Vec::with_capacity(4);
+    let mut candidates = vec![(row + 1, col), (row, col + 1)];
+    if row > 0 {
+        candidates.push((row - 1, col));
+    }
+    if col > 0 {
+        candidates.push((row, col - 1));
+    }
+    for cell in candidates {
+        if is_passable_with(maze, cell, doors) {
+            result.push(cell);
+        }
+    }
+    result
+}
+
+// Coste de entrar en una celda; todas cuestan lo mismo por ahora
+fn step_cost(_maze: &Maze, _cell: Cell) -> f32 {
+    1.0
+}
+
+fn manhattan(a: Cell, b: Cell) -> f32 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as f32
+}
+
+pub fn find_path(maze: &Maze, start: Cell, goal: Cell, algorithm: Algorithm) -> Option<Path> {
+    find_path_with(maze, start, goal, algorithm, Doors::Passable)
+}
+
+pub fn find_path_with(maze: &Maze, start: Cell, goal: Cell, algorithm: Algorithm, doors: Doors) -> Option<Path> {
+    if !is_passable_with(maze, start, doors) || !is_passable_with(maze, goal, doors) {
+        return None;
+    }
+    match algorithm {
+        Algorithm::Bfs => bfs(maze, start, goal, doors),
+        Algorithm::Dijkstra => best_first(maze, start, goal, false, doors),
+        // Con teletransportes la distancia Manhattan puede sobrestimar lo que falta y A* dejaría
+        // de dar el camino más corto
+        Algorithm::AStar => best_first(maze, start, goal, maze.teleporters.is_empty(), doors),
+    }
+}
+
+fn bfs(maze: &Maze, start: Cell, goal: Cell, doors: Doors) -> Option<Path> {
+    let mut came_from = vec![vec![None; maze.width()]; maze.height()];
+    let mut visited = vec![vec![false; maze.width()]; maze.height()];
+    let mut queue = VecDeque::new();
+    visited[start.0][start.1] = true;
+    queue.push_back(start);
+
+    while let Some(current) = queue.pop_front() {
+        if current == goal {
+            return Some(rebuild_path(maze, &came_from, start, goal));
+        }
+        for next in neighbors_with(maze, current, doors) {
+            if !visited[next.0][next.1] {
+                visited[next.0][next.1] = true;
+                came_from[next.0][next.1] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Dijkstra, o A* cuando `use_heuristic` está activo
+fn best_first(maze: &Maze, start: Cell, goal: Cell, use_heuristic: bool, doors: Doors) -> Option<Path> {
+    let mut came_from = vec![vec![None; maze.width()]; maze.height()];
+    let mut cost = vec![vec![f32::INFINITY; maze.width()]; maze.height()];
+    let mut open = BinaryHeap::new();
+    cost[start.0][start.1] = 0.0;
+    open.push(Node { priority: 0.0, cell: start });
+
+    while let Some(Node { cell: current, priority }) = open.pop() {
+        if current == goal {
+            return Some(rebuild_path(maze, &came_from, start, goal));
+        }
+        let current_cost = cost[current.0][current.1];
+        let heuristic = if use_heuristic { manhattan(current, goal) } else { 0.0 };
+        // Entrada obsoleta: ya se encontró un camino mejor a esta celda
+        if priority > current_cost + heuristic {
+            continue;
+        }
+        for next in neighbors_with(maze, current, doors) {
+            let new_cost = current_cost + step_cost(maze, next);
+            if new_cost < cost[next.0][next.1] {
+                cost[next.0][next.1] = new_cost;
+                came_from[next.0][next.1] = Some(current);
+                let heuristic = if use_heuristic { manhattan(next, goal) } else { 0.0 };
+                open.push(Node { priority: new_cost + heuristic, cell: next });
+            }
+        }
+    }
+    None
+}
+
+fn rebuild_path(maze: &Maze, came_from: &[Vec<Option<Cell>>], start: Cell, goal: Cell) -> Path {
+    let mut cells = vec![goal];
+    let mut current = goal;
+    while current != start {
+        match came_from[current.0][current.1] {
+            Some(previous) => {
+                cells.push(previous);
+                current = previous;
+            }
+            None => break,
+        }
+    }
+    cells.reverse();
+    let distance = cells.iter().skip(1).map(|&cell| step_cost(maze, cell)).sum();
+    Path { cells, distance }
+}
+
+// Distancia en pasos desde `start` a cada celda alcanzable
+pub fn distance_map(maze: &Maze, start: Cell) -> Vec<Vec<Option<u32>>> {
+    let mut distance = vec![vec![None; maze.width()]; maze.height()];
+    if !is_passable(maze, start) {
+        return distance;
+    }
+    let mut queue = VecDeque::new();
+    distance[start.0][start.1] = Some(0);
+    queue.push_back(start);
+
+    while let Some(current) = queue.pop_front() {
+        let steps = distance[current.0][current.1].unwrap_or(0);
+        for next in neighbors(maze, current) {
+            if distance[next.0][next.1].is_none() {
+                distance[next.0][next.1] = Some(steps + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distance
+}
+
+// Tiempo par del nivel: recorrer el camino más corto del inicio a la salida a velocidad máxima.
+// Se calcula con Dijkstra para que siga siendo exacto si las celdas llegan a tener costes distintos.
+pub fn par_time(maze: &Maze, player_speed: f32, block_size: i32) -> Option<f32> {
+    let exit = maze.exit?;
+    let path = find_path(maze, maze.start, exit, Algorithm::Dijkstra)?;
+    Some(path.distance * block_size as f32 / player_speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{self, Algorithm as Generator};
+
+    const ALGORITHMS: [Algorithm; 3] = [Algorithm::Bfs, Algorithm::Dijkstra, Algorithm::AStar];
+
+    // Cada paso del camino va a un vecino de la celda anterior
+    fn assert_connected(maze: &Maze, path: &Path, start: Cell, goal: Cell) {
+        assert_eq!(path.cells.first(), Some(&start));
+        assert_eq!(path.cells.last(), Some(&goal));
+        for pair in path.cells.windows(2) {
+            assert!(neighbors(maze, pair[0]).contains(&pair[1]), "{:?} -> {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn all_algorithms_find_equally_short_paths() {
+        for generator in Generator::ALL {
+            let maze = generator::generate(generator, 31, 21, 7);
+            let exit = maze.exit.unwrap();
+            let lengths: Vec<usize> = ALGORITHMS
+                .iter()
+                .map(|&algorithm| {
+                    let path = find_path(&maze, maze.start, exit, algorithm).unwrap();
+                    assert_connected(&maze, &path, maze.start, exit);
+                    assert_eq!(path.distance, (path.cells.len() - 1) as f32);
+                    path.cells.len()
+                })
+                .collect();
+            assert!(lengths.iter().all(|&length| length == lengths[0]), "{}: {:?}", generator.name(), lengths);
+            // Y coincide con el mapa de distancias
+            let steps = distance_map(&maze, maze.start)[exit.0][exit.1].unwrap();
+            assert_eq!(lengths[0], steps as usize + 1);
+        }
+    }
+
+    #[test]
+    fn walls_and_unknown_goals_have_no_path() {
+        let maze = Maze::parse("---\n#####\n# # #\n#####\n").unwrap();
+        for algorithm in ALGORITHMS {
+            assert!(find_path(&maze, (1, 1), (1, 3), algorithm).is_none());
+            assert!(find_path(&maze, (1, 1), (0, 0), algorithm).is_none());
+        }
+    }
+
+    #[test]
+    fn teleporters_shorten_paths() {
+        // El pasillo mide 9 pasos; el teletransporte de (1, 2) lleva directo a (1, 8)
+        let maze = Maze::parse("teleporte = 1 2 -> 1 8 0\n---\n###########\n# T       #\n#         #\n###########\n").unwrap();
+        for algorithm in ALGORITHMS {
+            let path = find_path(&maze, (1, 1), (1, 9), algorithm).unwrap();
+            assert_eq!(path.cells, vec![(1, 1), (1, 2), (1, 8), (1, 9)], "{:?}", algorithm);
+            assert_connected(&maze, &path, (1, 1), (1, 9));
+        }
+    }
+
+    #[test]
+    fn teleporter_cells_cannot_be_walked_across() {
+        // Pisar la `T` lleva a un callejón, así que para llegar al otro lado hay que rodearla
+        let maze = Maze::parse("teleporte = 1 2 -> 3 1 0\n---\n#####\n# T #\n#   #\n# ###\n#####\n").unwrap();
+        assert_eq!(neighbors(&maze, (1, 2)), vec![(3, 1)]);
+        for algorithm in ALGORITHMS {
+            let path = find_path(&maze, (1, 1), (1, 3), algorithm).unwrap();
+            assert!(!path.cells.contains(&(1, 2)), "{:?}: {:?}", algorithm, path.cells);
+            assert_eq!(path.cells.len(), 5);
+        }
+    }
+
+    #[test]
+    fn par_time_is_the_shortest_path_at_full_speed() {
+        let maze = Maze::parse("inicio = 1 1\n---\n#######\n#    E#\n#######\n").unwrap();
+        // Cuatro pasos de 64 píxeles a 128 píxeles por segundo
+        assert_eq!(par_time(&maze, 128.0, 64), Some(2.0));
+
+        let mut no_exit = maze;
+        no_exit.exit = None;
+        assert_eq!(par_time(&no_exit, 128.0, 64), None);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::doors::DOOR_THICKNESS;
+use crate::maze::{is_wall_char, Maze};
+
+// Colisiones del jugador como un círculo contra las celdas de muro de la cuadrícula.
+// En lugar de bloquear el eje que choca, el círculo se empuja fuera de cada muro por la
+// normal del punto más cercano: se pierde solo la componente que entra en la pared y la
+// paralela se conserva, así que el jugador se desliza por ella. Las puertas que no están
+// abiertas del todo son una plancha fina en el centro de su celda.
+
+// Penetraciones menores se ignoran para que ir pegado a una pared no cuente como choque
+const CONTACT_EPSILON: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Movement {
+    pub x: f32,
+    pub y: f32,
+    // Muro que más frenó el movimiento, si alguno lo hizo
+    pub wall: Option<char>,
+    // Distancia del avance que se comieron los muros: todo el avance al chocar de frente,
+    // solo la componente perpendicular al deslizarse
+    pub impact: f32,
+}
+
+// Contacto más fuerte de una pasada de `push_out`
+struct Contact {
+    wall: char,
+    push: f32,
+}
+
+pub fn move_circle(maze: &Maze, x: f32, y: f32, dx: f32, dy: f32, radius: f32, block_size: i32) -> Movement {
+    // Pasos de como mucho medio radio para no atravesar muros ni esquinas en frames largos
+    let distance = (dx * dx + dy * dy).sqrt();
+    let steps = ((distance / (radius * 0.5)).ceil() as usize).max(1);
+    let (step_x, step_y) = (dx / steps as f32, dy / steps as f32);
+
+    let (mut x, mut y) = (x, y);
+    let mut wall = None;
+    let mut strongest = 0.0;
+    let mut impact = 0.0;
+    for _ in 0..steps {
+        x += step_x;
+        y += step_y;
+        // En un rincón, salir de un muro puede meter el círculo en el vecino; con dos pasadas basta
+        for _ in 0..2 {
+            let (start_x, start_y) = (x, y);
+            let (next_x, next_y, contact) = push_out(maze, x, y, radius, block_size);
+            x = next_x;
+            y = next_y;
+            let Some(contact) = contact else {
+                break;
+            };
+            impact += ((x - start_x).powi(2) + (y - start_y).powi(2)).sqrt();
+            if wall.is_none() || contact.push > strongest {
+                wall = Some(contact.wall);
+                strongest = contact.push;
+            }
+        }
+    }
+    Movement { x, y, wall, impact }
+}
+
+// Separa el círculo de los muros que toca; devuelve la nueva posición y el contacto más fuerte
+fn push_out(maze: &Maze, x: f32, y: f32, radius: f32, block_size: i32) -> (f32, f32, Option<Contact>) {
+    let size = block_size as f32;
+    let (mut x, mut y) = (x, y);
+    let mut strongest: Option<Contact> = None;
+
+    let min_col = ((x - radius) / size).floor() as i64;
+    let max_col = ((x + radius) / size).floor() as i64;
+    let min_row = ((y - radius) / size).floor() as i64;
+    let max_row = ((y + radius) / size).floor() as i64;
+
+    for row in min_row..=max_row {
+        for col in min_col..=max_col {
+            let Some((left, top, right, bottom)) = solid_rect(maze, row, col, size) else {
+                continue;
+            };
+
+            // Punto del muro más cercano al centro del círculo
+            let mut offset_x = x - x.clamp(left, right);
+            let mut offset_y = y - y.clamp(top, bottom);
+            // Si cae en una esquina cuyo lado sigue en otro muro (la junta entre dos bloques de
+            // una pared recta), solo cuenta la separación en el otro eje; si no, empujaría en diagonal
+            if offset_x != 0.0 && offset_y != 0.0 {
+                if is_wall_at(maze, row, col + offset_x.signum() as i64) {
+                    offset_x = 0.0;
+                }
+                if is_wall_at(maze, row + offset_y.signum() as i64, col) {
+                    offset_y = 0.0;
+                }
+                // Esquina interior: de ella se encargan los dos muros vecinos
+                if offset_x == 0.0 && offset_y == 0.0 {
+                    continue;
+                }
+            }
+            let distance_sq = offset_x * offset_x + offset_y * offset_y;
+            let contact = radius - CONTACT_EPSILON;
+            if distance_sq >= contact * contact {
+                continue;
+            }
+
+            let push = if distance_sq > 0.0 {
+                let distance = distance_sq.sqrt();
+                let push = radius - distance;
+                x += offset_x / distance * push;
+                y += offset_y / distance * push;
+                push
+            } else {
+                // El centro quedó dentro del muro: sale por el lado más cercano
+                let exits = [
+                    (left - radius - x, 0.0),
+                    (right + radius - x, 0.0),
+                    (0.0, top - radius - y),
+                    (0.0, bottom + radius - y),
+                ];
+                let (exit_x, exit_y) = exits
+                    .into_iter()
+                    .min_by(|a, b| (a.0.abs() + a.1.abs()).total_cmp(&(b.0.abs() + b.1.abs())))
+                    .unwrap();
+                x += exit_x;
+                y += exit_y;
+                exit_x.abs() + exit_y.abs()
+            };
+            if strongest.as_ref().is_none_or(|contact| push > contact.push) {
+                strongest = Some(Contact { wall: cell_at(maze, row, col), push });
+            }
+        }
+    }
+    (x, y, strongest)
+}
+
+// Parte sólida de la celda: el bloque entero si es muro o la plancha de una puerta cerrada
+fn solid_rect(maze: &Maze, row: i64, col: i64, size: f32) -> Option<(f32, f32, f32, f32)> {
+    let (left, top) = (col as f32 * size, row as f32 * size);
+    if is_wall_at(maze, row, col) {
+        return Some((left, top, left + size, top + size));
+    }
+    let door = maze.door(row as usize, col as usize).filter(|door| door.is_blocking())?;
+    let (center, half) = (0.5 * size, 0.5 * DOOR_THICKNESS * size);
+    if door.vertical {
+        Some((left + center - half, top, left + center + half, top + size))
+    } else {
+        Some((left, top + center - half, left + size, top + center + half))
+    }
+}
+
+// Fuera del mapa (también por arriba o por la izquierda) se considera muro
+fn cell_at(maze: &Maze, row: i64, col: i64) -> char {
+    if row < 0 || col < 0 { '#' } else { maze.cell(row as usize, col as usize) }
+}
+
+// Solo muros: en las juntas, una puerta al lado no continúa la pared
+fn is_wall_at(maze: &Maze, row: i64, col: i64) -> bool {
+    is_wall_char(cell_at(maze, row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: i32 = 64;
+    const RADIUS: f32 = 12.0;
+
+    // Sala de 3x3 celdas libres, de 64 a 256 px en cada eje
+    fn room() -> Maze {
+        Maze::parse("---\n#####\n#   #\n#   #\n#   #\n#####\n").unwrap()
+    }
+
+    fn corridor() -> Maze {
+        Maze::parse("---\n#####\n#   #\n#####\n").unwrap()
+    }
+
+    fn assert_clear_of_walls(maze: &Maze, movement: Movement) {
+        let size = BLOCK as f32;
+        for row in 0..maze.height() {
+            for col in 0..maze.width() {
+                if !maze.is_wall_cell(row, col) {
+                    continue;
+                }
+                let (left, top) = (col as f32 * size, row as f32 * size);
+                let offset_x = movement.x - movement.x.clamp(left, left + size);
+                let offset_y = movement.y - movement.y.clamp(top, top + size);
+                let distance = (offset_x * offset_x + offset_y * offset_y).sqrt();
+                assert!(distance >= RADIUS - 0.02, "a {} del muro ({}, {})", distance, row, col);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_freely_in_open_space() {
+        let movement = move_circle(&room(), 160.0, 160.0, 20.0, -10.0, RADIUS, BLOCK);
+        assert_eq!(movement.wall, None);
+        assert!((movement.x - 180.0).abs() < 1e-3);
+        assert!((movement.y - 150.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stops_one_radius_from_a_wall() {
+        let movement = move_circle(&room(), 160.0, 160.0, 200.0, 0.0, RADIUS, BLOCK);
+        assert!(movement.wall.is_some());
+        assert!((movement.x - (256.0 - RADIUS)).abs() < 1e-3);
+        assert!((movement.y - 160.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn slides_along_a_wall() {
+        // En diagonal contra la pared de arriba: el avance en x se conserva entero
+        let movement = move_circle(&corridor(), 100.0, 64.0 + RADIUS, 30.0, -30.0, RADIUS, BLOCK);
+        assert!(movement.wall.is_some());
+        assert!((movement.x - 130.0).abs() < 1e-3);
+        assert!((movement.y - (64.0 + RADIUS)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn moving_parallel_to_a_wall_is_not_a_collision() {
+        let maze = corridor();
+        let mut movement = Movement { x: 80.0, y: 64.0 + RADIUS, wall: None, impact: 0.0 };
+        for _ in 0..20 {
+            movement = move_circle(&maze, movement.x, movement.y, 5.0, 0.0, RADIUS, BLOCK);
+            assert_eq!(movement.wall, None);
+        }
+        assert!((movement.x - 180.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stays_out_of_corners() {
+        let maze = room();
+        let movement = move_circle(&maze, 160.0, 160.0, -150.0, -150.0, RADIUS, BLOCK);
+        assert!(movement.wall.is_some());
+        assert!((movement.x - (64.0 + RADIUS)).abs() < 1e-3);
+        assert!((movement.y - (64.0 + RADIUS)).abs() < 1e-3);
+        assert_clear_of_walls(&maze, movement);
+    }
+
+    #[test]
+    fn rounds_convex_corners() {
+        // Un pilar suelto: al rozar su esquina el círculo la rodea en vez de engancharse
+        let maze = Maze::parse("---\n#####\n#   #\n# # #\n#   #\n#####\n").unwrap();
+        let start_y = 128.0 - RADIUS * 0.5;
+        let movement = move_circle(&maze, 100.0, start_y, 60.0, 0.0, RADIUS, BLOCK);
+        assert!(movement.wall.is_some());
+        assert!(movement.x > 140.0);
+        assert!(movement.y < start_y);
+        assert_clear_of_walls(&maze, movement);
+    }
+
+    #[test]
+    fn fast_moves_do_not_tunnel_through_walls() {
+        let maze = Maze::parse("---\n#######\n#  #  #\n#######\n").unwrap();
+        let movement = move_circle(&maze, 96.0, 96.0, 200.0, 0.0, RADIUS, BLOCK);
+        assert!(movement.wall.is_some());
+        assert!(movement.x < 192.0);
+        assert_clear_of_walls(&maze, movement);
+    }
+
+    #[test]
+    fn pushes_out_when_starting_inside_a_wall() {
+        let maze = room();
+        let movement = move_circle(&maze, 60.0, 160.0, 0.0, 0.0, RADIUS, BLOCK);
+        assert!(movement.wall.is_some());
+        assert_clear_of_walls(&maze, movement);
+    }
+
+    #[test]
+    fn closed_doors_block_at_their_plane_and_open_ones_do_not() {
+        // Puerta vertical en la columna 2, con muros arriba y abajo
+        let mut maze = corridor();
+        maze.grid[1][2] = 'P';
+        maze = Maze::parse(&maze.to_level_string()).unwrap();
+        assert!(maze.doors[0].vertical);
+        let plane = 2.5 * BLOCK as f32;
+        let closed = move_circle(&maze, 96.0, 96.0, 100.0, 0.0, RADIUS, BLOCK);
+        assert_eq!(closed.wall, Some('P'));
+        assert!((closed.x - (plane - 0.5 * DOOR_THICKNESS * BLOCK as f32 - RADIUS)).abs() < 1e-3);
+
+        maze.doors[0].set_open(true);
+        let open = move_circle(&maze, 96.0, 96.0, 100.0, 0.0, RADIUS, BLOCK);
+        assert_eq!(open.wall, None);
+        assert!((open.x - 196.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn reports_the_wall_and_how_hard_it_was_hit() {
+        let maze = Maze::parse("---\n#####\n#   #\n#DDD#\n").unwrap();
+        let head_on = move_circle(&maze, 160.0, 100.0, 0.0, 20.0, RADIUS, BLOCK);
+        assert_eq!(head_on.wall, Some('D'));
+        assert!((head_on.impact - 4.0).abs() < 1e-3);
+
+        // Al deslizarse solo cuenta la componente que entra en la pared
+        let graze = move_circle(&maze, 100.0, 128.0 - RADIUS, 20.0, 2.0, RADIUS, BLOCK);
+        assert_eq!(graze.wall, Some('D'));
+        assert!((graze.impact - 2.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::pathfinding::Cell;
+use crate::player::Player;
+
+// Objetos que se recogen al pasar por encima (`objeto = 4 7 moneda` en el nivel)
+
+// Distancia en píxeles a la que se recoge un objeto o una llave, sumada al radio del jugador
+const PICKUP_RADIUS: f32 = 16.0;
+
+// Segundos que duran la invulnerabilidad y el aumento de velocidad
+pub const INVULNERABILITY_TIME: f64 = 5.0;
+pub const SPEED_BOOST_TIME: f64 = 6.0;
+
+// Multiplicador de la velocidad del jugador mientras dura el aumento
+pub const SPEED_BOOST: f32 = 1.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickupKind {
+    // Suma puntos al terminar el nivel
+    Coin,
+    // Devuelve un corazón sin pasar de la salud máxima
+    Heart,
+    Invulnerability,
+    Speed,
+    // Descubre todo el minimapa
+    Map,
+}
+
+impl PickupKind {
+    pub const ALL: [PickupKind; 5] = [PickupKind::Coin, PickupKind::Heart, PickupKind::Invulnerability, PickupKind::Speed, PickupKind::Map];
+
+    // Nombre en el nivel; también es el tipo de sprite con el que se dibuja
+    pub fn name(self) -> &'static str {
+        match self {
+            PickupKind::Coin => "moneda",
+            PickupKind::Heart => "corazon",
+            PickupKind::Invulnerability => "escudo",
+            PickupKind::Speed => "velocidad",
+            PickupKind::Map => "mapa",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PickupKind> {
+        PickupKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pickup {
+    pub row: usize,
+    pub col: usize,
+    pub kind: PickupKind,
+}
+
+// El círculo del jugador llega al centro de la celda (`row`, `col`); vale para objetos y llaves
+pub fn in_reach(player: &Player, (row, col): Cell, block_size: i32) -> bool {
+    let size = block_size as f32;
+    let (x, y) = ((col as f32 + 0.5) * size, (row as f32 + 0.5) * size);
+    (x - player.x).hypot(y - player.y) < player.radius + PICKUP_RADIUS
+}
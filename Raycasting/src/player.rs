@@ -1,10 +1,37 @@
+use crate::doors::KeyColor;
+use crate::maze::Maze;
+use crate::physics::{move_circle, Movement};
+
 pub struct Player {
     pub x: f32,
     pub y: f32,
     pub angle: f32,
     pub speed: f32,
-    pub lives: i32,
-    pub max_lives: i32,
+    // Radio del círculo con el que choca contra las paredes
+    pub radius: f32,
+    // Salud en corazones: con la política de vidas cada choque quita uno entero
+    pub health: f32,
+    pub max_health: f32,
+    pub inventory: Inventory,
+}
+
+// Objetos que lleva el jugador; se vacía al empezar cada nivel
+#[derive(Debug, Clone, Default)]
+pub struct Inventory {
+    // Llaves recogidas, sin repetir y en el orden de `KeyColor`
+    pub keys: Vec<KeyColor>,
+}
+
+impl Inventory {
+    pub fn has_key(&self, color: KeyColor) -> bool {
+        self.keys.contains(&color)
+    }
+
+    pub fn add_key(&mut self, color: KeyColor) {
+        if let Err(index) = self.keys.binary_search(&color) {
+            self.keys.insert(index, color);
+        }
+    }
 }
 
 impl Player {
@@ -14,28 +41,51 @@ impl Player {
             y,
             angle: 0.0,
             speed: 150.0,
-            lives: 3,
-            max_lives: 3,
+            radius: 12.0,
+            health: 3.0,
+            max_health: 3.0,
+            inventory: Inventory::default(),
         }
     }
 
-    pub fn reset_position(&mut self, start_col: usize, start_row: usize, block_size: i32) {
+    pub fn reset_position(&mut self, start_col: usize, start_row: usize, start_angle: f32, block_size: i32) {
         self.x = (start_col as f32 + 0.5) * block_size as f32;
         self.y = (start_row as f32 + 0.5) * block_size as f32;
-        self.angle = 0.0;
+        self.angle = start_angle;
     }
 
-    pub fn lose_life(&mut self) {
-        if self.lives > 0 {
-            self.lives -= 1;
+    // Desplazamiento del frame a partir del avance y el movimiento lateral (-1..1 cada uno).
+    // En diagonal el vector se normaliza para no superar `speed`; con stick se respeta la inclinación
+    pub fn movement(&self, forward: f32, strafe: f32, dt: f32) -> (f32, f32) {
+        let length = (forward * forward + strafe * strafe).sqrt();
+        if length == 0.0 {
+            return (0.0, 0.0);
         }
+        let scale = self.speed * dt / length.max(1.0);
+        let (sin, cos) = self.angle.sin_cos();
+        // El lateral derecho es el ángulo + 90°
+        let dx = (cos * forward - sin * strafe) * scale;
+        let dy = (sin * forward + cos * strafe) * scale;
+        (dx, dy)
+    }
+
+    // Mueve al jugador deslizándose por las paredes; el resultado dice si alguna lo frenó
+    pub fn move_and_collide(&mut self, maze: &Maze, dx: f32, dy: f32, block_size: i32) -> Movement {
+        let movement = move_circle(maze, self.x, self.y, dx, dy, self.radius, block_size);
+        self.x = movement.x;
+        self.y = movement.y;
+        movement
+    }
+
+    pub fn take_damage(&mut self, amount: f32) {
+        self.health = (self.health - amount).max(0.0);
     }
 
     pub fn is_alive(&self) -> bool {
-        self.lives > 0
+        self.health > 0.0
     }
 
-    pub fn reset_lives(&mut self) {
-        self.lives = self.max_lives;
+    pub fn reset_health(&mut self) {
+        self.health = self.max_health;
     }
 }
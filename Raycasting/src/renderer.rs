@@ -1,168 +1,744 @@
 use raylib::prelude::*;
-use crate::maze::{MAZE, wall_color};
+use std::f32::consts::PI;
+use crate::doors::{Door, Key};
+use crate::enemies::{Enemy, ENEMY_SPRITE};
+use crate::pickups::Pickup;
+use crate::maze::{is_wall_char, Maze, CEILING_HEIGHT, EYE_HEIGHT};
+use crate::pathfinding::cell_at;
 use crate::player::Player;
 use crate::framebuffer::Framebuffer;
+use crate::textures::{Texture, TextureSet, shade};
 
-pub fn render_scene(
-    framebuffer: &mut Framebuffer,
-    player: &Player,
+// Objeto del mundo dibujado como sprite orientado siempre hacia la cámara
+#[derive(Clone)]
+pub struct Sprite {
+    pub x: f32,
+    pub y: f32,
+    pub kind: String,
+    // Altura en bloques; los sprites se apoyan en el suelo
+    pub scale: f32,
+}
+
+impl Sprite {
+    pub fn from_decorations(maze: &Maze, block_size: i32) -> Vec<Sprite> {
+        maze.decorations
+            .iter()
+            .map(|decoration| Sprite {
+                x: (decoration.col as f32 + 0.5) * block_size as f32,
+                y: (decoration.row as f32 + 0.5) * block_size as f32,
+                kind: decoration.kind.clone(),
+                scale: 0.8,
+            })
+            .collect()
+    }
+
+    pub fn from_keys(keys: &[Key], block_size: i32) -> Vec<Sprite> {
+        keys.iter()
+            .map(|key| Sprite {
+                x: (key.col as f32 + 0.5) * block_size as f32,
+                y: (key.row as f32 + 0.5) * block_size as f32,
+                kind: key.color.sprite().to_string(),
+                scale: 0.5,
+            })
+            .collect()
+    }
+
+    pub fn from_pickups(pickups: &[Pickup], block_size: i32) -> Vec<Sprite> {
+        pickups
+            .iter()
+            .map(|pickup| Sprite {
+                x: (pickup.col as f32 + 0.5) * block_size as f32,
+                y: (pickup.row as f32 + 0.5) * block_size as f32,
+                kind: pickup.kind.name().to_string(),
+                scale: 0.4,
+            })
+            .collect()
+    }
+
+    pub fn from_enemies(enemies: &[Enemy]) -> Vec<Sprite> {
+        enemies
+            .iter()
+            .map(|enemy| Sprite {
+                x: enemy.x,
+                y: enemy.y,
+                kind: ENEMY_SPRITE.to_string(),
+                scale: 0.9,
+            })
+            .collect()
+    }
+}
+
+// Lo que encuentra el rayo de una columna, ya proyectado a pantalla
+#[derive(Clone, Default)]
+struct Column {
+    // De cerca a lejos; se pintan al revés para que lo cercano tape lo lejano
+    slices: Vec<Slice>,
+    // Filas que cubren entre todos; en el resto se ven el techo y el suelo de fondo
+    top: i32,
+    bottom: i32,
+}
+
+// Trozo de una columna que cubre las filas `top..bottom`
+#[derive(Clone)]
+struct Slice {
+    top: i32,
+    bottom: i32,
+    surface: Surface,
+}
+
+#[derive(Clone)]
+enum Surface {
+    // Cara vertical de una pared, una puerta o un escalón (que lleva la textura del suelo de su
+    // celda) a `distance` píxeles. `texture_top` es la fila en la que queda la altura 1 y `block`
+    // lo que mide un bloque en pantalla, para que la textura no se estire en las paredes bajas
+    Face { cell: char, step: bool, wall_x: f32, distance: f32, texture_top: i32, block: i32, factor: f32 },
+    // Cara de arriba de una celda a `height`: suelo, plataforma o lo alto de un muro bajo
+    Top { cell: char, height: f32, ray: Ray },
+}
+
+// Tramo recto de un rayo, en píxeles: sale de (`x`, `y`) hacia (`dx`, `dy`) cuando el rayo ya ha
+// recorrido `travelled`. Cada portal que cruza empieza uno nuevo
+#[derive(Clone, Copy)]
+struct Ray {
+    x: f32,
+    y: f32,
+    dx: f32,
+    dy: f32,
+    travelled: f32,
+}
+
+// Punto en que el rayo corta una cara vertical: distancia en píxeles, posición a lo ancho de la
+// cara (de 0 a 1) y si es una cara norte o sur
+#[derive(Clone, Copy)]
+struct Hit {
+    distance: f32,
+    wall_x: f32,
+    side: bool,
+}
+
+// Margen, en bloques, con el que el suelo tapa a un sprite; sin él se comería la fila de los pies
+const SPRITE_FLOOR_MARGIN: f32 = 0.5;
+
+// Sprite proyectado a pantalla, listo para dibujarse en cualquier franja
+struct ProjectedSprite<'a> {
+    texture: &'a Texture,
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    floor_y: f32,
+    distance: f32,
+    factor: f32,
+}
+
+// Datos del fotograma compartidos (solo lectura) por todos los hilos
+struct Frame<'a> {
+    maze: &'a Maze,
+    textures: &'a TextureSet,
+    player: &'a Player,
     fov: f32,
     block_size: i32,
-    time: f64,
-    invulnerability_time: f64,
     screen_width: i32,
     screen_height: i32,
+    flash: bool,
+    exit_color: Color,
+    ray_dirs: Vec<(f32, f32)>,
+    // Altura de los ojos, que suben y bajan con el suelo que se pisa
+    eye: f32,
+}
+
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+// Cómo se ve la escena, además del nivel y el jugador
+#[derive(Debug, Clone, Copy)]
+pub struct SceneView {
+    pub fov: f32,
+    pub block_size: i32,
+    // Tiempo de partida, para las animaciones
+    pub time: f64,
+    // Mientras dura, la escena parpadea
+    pub invulnerability_time: f64,
+    pub screen_width: i32,
+    pub screen_height: i32,
+    pub threads: usize,
+}
+
+// Renderiza la escena en dos fases: primero se lanza un rayo por columna y después se
+// rellenan los píxeles por franjas horizontales. Ambas fases se reparten entre `threads`
+// hilos; cada píxel se calcula igual sin importar el hilo, así que el resultado es
+// idéntico al de un solo hilo.
+pub fn render_scene(
+    framebuffer: &mut Framebuffer,
+    maze: &Maze,
+    textures: &TextureSet,
+    sprites: &[Sprite],
+    player: &Player,
+    view: SceneView,
 ) {
-    // Limpiar framebuffer
-    framebuffer.clear();
-
-    // Renderizar cielo y piso
-    let sky_color = Color::new(66, 135, 245, 255);
-    let floor_color = Color::new(180, 180, 180, 255);
-    let sky_height = (screen_height as f32 * 0.25) as i32;
-    
-    // Dibujar cielo
-    for y in 0..sky_height {
-        for x in 0..screen_width {
-            framebuffer.set_pixel_color(x as u32, y as u32, sky_color);
-        }
-    }
-    
-    // Dibujar piso
-    for y in sky_height..screen_height {
-        for x in 0..screen_width {
-            framebuffer.set_pixel_color(x as u32, y as u32, floor_color);
-        }
-    }
-
-    // Raycasting vertical por columnas
-    for x in 0..screen_width {
-        let ray_angle = player.angle - fov / 2.0 + (x as f32 / screen_width as f32) * fov;
-        let (distance, wall_type, is_side) = cast_ray(player.x, player.y, ray_angle, block_size);
-        let wall_height = (screen_height as f32 * block_size as f32 / distance.max(1.0)) as i32;
-        let wall_top = (screen_height / 2) - wall_height / 2;
-        let wall_bottom = wall_top + wall_height;
-
-        let mut col = match wall_type {
-            '#' => wall_color(wall_type),
-            'A' => wall_color(wall_type),
-            'B' => wall_color(wall_type),
-            'C' => wall_color(wall_type),
-            'D' => wall_color(wall_type),
-            'E' => {
-                let t = ((time * 2.0).sin() * 0.5 + 0.5) as f32;
-                Color::new(
-                    (255.0 * (1.0 - t) + 255.0 * t) as u8,
-                    (99.0 * (1.0 - t) + 255.0 * t) as u8,
-                    (130.0 * (1.0 - t) + 255.0 * t) as u8,
-                    255,
-                )
+    let SceneView { fov, block_size, time, invulnerability_time, screen_width, screen_height, threads } = view;
+    let threads = threads.max(1);
+    let t = ((time * 2.0).sin() * 0.5 + 0.5) as f32;
+    let frame = Frame {
+        maze,
+        textures,
+        player,
+        fov,
+        block_size,
+        screen_width,
+        screen_height,
+        flash: invulnerability_time > 0.0 && ((time * 10.0) as i32 % 2 == 0),
+        exit_color: Color::new(
+            (255.0 * (1.0 - t) + 255.0 * t) as u8,
+            (99.0 * (1.0 - t) + 255.0 * t) as u8,
+            (130.0 * (1.0 - t) + 255.0 * t) as u8,
+            255,
+        ),
+        ray_dirs: (0..screen_width)
+            .map(|x| {
+                let ray_angle = ray_angle(player, fov, x, screen_width);
+                (ray_angle.cos(), ray_angle.sin())
+            })
+            .collect(),
+        eye: floor_height(maze, player.x, player.y, block_size) + EYE_HEIGHT,
+    };
+
+    // Fase 1: un rayo por columna
+    let mut columns = vec![Column::default(); screen_width as usize];
+    let columns_per_thread = columns.len().div_ceil(threads).max(1);
+    run_parallel(columns.chunks_mut(columns_per_thread).enumerate(), |(index, chunk)| {
+        cast_columns(&frame, index * columns_per_thread, chunk);
+    });
+
+    let projected = project_sprites(&frame, sprites, time);
+
+    // Fase 2: píxeles por franjas horizontales contiguas en memoria
+    let width = framebuffer.width as usize;
+    let rows_per_strip = (framebuffer.height as usize).div_ceil(threads).max(1);
+    run_parallel(framebuffer.pixels.chunks_mut(rows_per_strip * width).enumerate(), |(index, strip)| {
+        shade_strip(&frame, &columns, &projected, index * rows_per_strip, strip);
+    });
+    framebuffer.mark_dirty();
+}
+
+// Ejecuta `work` para cada elemento; con un solo elemento no se crea ningún hilo
+fn run_parallel<I, F>(items: I, work: F)
+where
+    I: Iterator,
+    I::Item: Send,
+    F: Fn(I::Item) + Sync,
+{
+    let items: Vec<I::Item> = items.collect();
+    if items.len() <= 1 {
+        items.into_iter().for_each(&work);
+        return;
+    }
+    std::thread::scope(|scope| {
+        for item in items {
+            let work = &work;
+            scope.spawn(move || work(item));
+        }
+    });
+}
+
+// Ángulo del rayo de la columna `x`: las columnas reparten el campo de visión a partes iguales
+fn ray_angle(player: &Player, fov: f32, x: i32, screen_width: i32) -> f32 {
+    player.angle - fov / 2.0 + (x as f32 / screen_width as f32) * fov
+}
+
+fn cast_columns(frame: &Frame, first_x: usize, columns: &mut [Column]) {
+    for (offset, column) in columns.iter_mut().enumerate() {
+        let angle = ray_angle(frame.player, frame.fov, (first_x + offset) as i32, frame.screen_width);
+        *column = cast_column(frame, angle);
+    }
+}
+
+// Portales que puede cruzar el rayo de una columna seguidos; después se da por bloqueado, por si
+// dos portales se ven el uno al otro
+const MAX_PORTALS: usize = 8;
+
+// Lanza el rayo de una columna hasta una pared que llegue al techo. Pasa por encima de los muros
+// bajos y de los cambios de altura del suelo, y de cada celda que cruza guarda la cara de arriba
+// y, si sube respecto a la anterior, la cara vertical por la que entra
+fn cast_column(frame: &Frame, angle: f32) -> Column {
+    let maze = frame.maze;
+    let size = frame.block_size as f32;
+    let mut column = Column::default();
+    let (mut x, mut y, mut angle) = (frame.player.x / size, frame.player.y / size, angle);
+    let mut travelled = 0.0;
+    // Dentro de un muro (ver `floor_height`) se parte de suelo
+    let (row, col) = cell_at(frame.player.x, frame.player.y, frame.block_size);
+    let mut cell = if maze.is_wall_cell(row, col) { ' ' } else { maze.cell(row, col) };
+    let mut height = floor_height(maze, frame.player.x, frame.player.y, frame.block_size);
+
+    'segments: for _ in 0..=MAX_PORTALS {
+        let mut dda = Dda::new(x, y, angle);
+        // El primer tramo sale del jugador tal cual, para que el suelo coincida con el de fondo
+        let (ray_x, ray_y) = if travelled == 0.0 { (frame.player.x, frame.player.y) } else { (x * size, y * size) };
+        let ray = Ray { x: ray_x, y: ray_y, dx: dda.dx, dy: dda.dy, travelled: travelled * size };
+        let mut entry = 0.0;
+        loop {
+            let exit = dda.advance();
+            let distance = (travelled + exit) * size;
+            column.push_top(frame, cell, height, ray, (travelled + entry) * size, distance);
+            let Some((row, col)) = dda.cell(maze) else {
+                column.push_face(frame, '#', false, height, CEILING_HEIGHT, dda.hit(distance));
+                break 'segments;
+            };
+            let next = maze.cell(row, col);
+            let next_height = maze.cell_height(row, col);
+
+            if let Some(door) = maze.door(row, col)
+                && let Some((door_distance, wall_x)) = door_hit(door, x, y, dda.dx, dda.dy)
+            {
+                let door_distance = (travelled + door_distance) * size;
+                column.push_face(frame, next, true, height, next_height, dda.hit(distance));
+                column.push_top(frame, next, next_height, ray, distance, door_distance);
+                let hit = Hit { distance: door_distance, wall_x, side: !door.vertical };
+                column.push_face(frame, next, false, next_height, CEILING_HEIGHT, hit);
+                break 'segments;
+            }
+            if let Some(portal) = maze.teleporter(row, col).filter(|teleporter| teleporter.is_portal()) {
+                (x, y, angle) = portal.transform(x + dda.dx * exit, y + dda.dy * exit, angle, 1.0);
+                travelled += exit;
+                let (row, col) = portal.to;
+                let (portal_cell, portal_height) = (maze.cell(row, col), maze.cell_height(row, col));
+                // Si el destino está más alto, el escalón se ve en el propio portal
+                column.push_face(frame, portal_cell, true, height, portal_height, dda.hit(distance));
+                (cell, height) = (portal_cell, portal_height);
+                continue 'segments;
+            }
+
+            let solid = is_wall_char(next) || next == 'E';
+            column.push_face(frame, next, !solid, height, next_height, dda.hit(distance));
+            if next_height >= CEILING_HEIGHT {
+                break 'segments;
+            }
+            (cell, height, entry) = (next, next_height, exit);
+        }
+    }
+    column
+}
+
+impl Column {
+    fn push(&mut self, frame: &Frame, top: i32, bottom: i32, surface: Surface) {
+        let (top, bottom) = (top.clamp(0, frame.screen_height), bottom.clamp(0, frame.screen_height));
+        if top >= bottom {
+            return;
+        }
+        if self.slices.is_empty() {
+            (self.top, self.bottom) = (top, bottom);
+        } else {
+            (self.top, self.bottom) = (self.top.min(top), self.bottom.max(bottom));
+        }
+        self.slices.push(Slice { top, bottom, surface });
+    }
+
+    // Cara vertical a `distance` píxeles desde la altura `bottom` hasta `top`; de bajada no se ve
+    fn push_face(&mut self, frame: &Frame, cell: char, step: bool, bottom: f32, top: f32, hit: Hit) {
+        if top <= bottom {
+            return;
+        }
+        let Hit { distance, wall_x, side } = hit;
+        let block = (frame.screen_height as f32 * frame.block_size as f32 / distance.max(1.0)) as i32;
+
+        // Oscurecimiento por cara lateral, distancia e invulnerabilidad
+        let mut factor = if side { 0.7 } else { 1.0 };
+        factor *= distance_fade(distance, frame.block_size);
+        if frame.flash {
+            factor *= 0.5;
+        }
+
+        let texture_top = screen_row(frame, CEILING_HEIGHT, block);
+        let surface = Surface::Face { cell, step, wall_x, distance, texture_top, block, factor };
+        self.push(frame, screen_row(frame, top, block), screen_row(frame, bottom, block), surface);
+    }
+
+    // Cara de arriba de una celda entre las distancias `near` y `far`; solo se ve desde encima
+    fn push_top(&mut self, frame: &Frame, cell: char, height: f32, ray: Ray, near: f32, far: f32) {
+        if height >= frame.eye {
+            return;
+        }
+        let block = |distance: f32| (frame.screen_height as f32 * frame.block_size as f32 / distance.max(1.0)) as i32;
+        let (top, bottom) = (screen_row(frame, height, block(far)), screen_row(frame, height, block(near)));
+        self.push(frame, top, bottom, Surface::Top { cell, height, ray });
+    }
+
+    // El sprite a `distance` queda detrás de lo que se ve en la fila `y`
+    fn hides(&self, frame: &Frame, y: i32, distance: f32) -> bool {
+        // El primero que cubre la fila es el más cercano, el que se ve
+        let Some(slice) = self.slices.iter().find(|slice| (slice.top..slice.bottom).contains(&y)) else {
+            return false;
+        };
+        match slice.surface {
+            Surface::Face { distance: wall, .. } => wall <= distance,
+            Surface::Top { height, .. } => {
+                plane_distance(frame, height, y) < distance - frame.block_size as f32 * SPRITE_FLOOR_MARGIN
             }
-            _ => Color::WHITE,
+        }
+    }
+}
+
+// Altura del suelo bajo el punto (`x`, `y`), en píxeles. Dentro de un muro, que solo pasa al
+// renderizar desde la línea de comandos, se toma el suelo a 0
+fn floor_height(maze: &Maze, x: f32, y: f32, block_size: i32) -> f32 {
+    let (row, col) = cell_at(x, y, block_size);
+    if maze.is_wall_cell(row, col) { 0.0 } else { maze.cell_height(row, col) }
+}
+
+// Primera fila de pantalla por debajo de la altura `height` en una pared en la que un bloque mide
+// `block` píxeles
+fn screen_row(frame: &Frame, height: f32, block: i32) -> i32 {
+    ((frame.screen_height / 2) as f32 - (height - frame.eye) * block as f32).ceil() as i32
+}
+
+// Distancia a la que se ve en la fila `y` el plano horizontal a `height`, en la misma métrica que
+// las paredes, para que el suelo encaje con su base
+fn plane_distance(frame: &Frame, height: f32, y: i32) -> f32 {
+    let offset = (y as f32 + 0.5 - frame.screen_height as f32 / 2.0).abs();
+    frame.screen_height as f32 * frame.block_size as f32 * (frame.eye - height).abs() / offset
+}
+
+fn project_sprites<'a>(frame: &Frame<'a>, sprites: &[Sprite], time: f64) -> Vec<ProjectedSprite<'a>> {
+    let player = frame.player;
+    let fov = frame.fov;
+    let screen_width = frame.screen_width as f32;
+    let screen_height = frame.screen_height as f32;
+    let block_size = frame.block_size as f32;
+    let mut projected = Vec::new();
+
+    for sprite in sprites {
+        let distance = (sprite.x - player.x).hypot(sprite.y - player.y);
+        if distance <= 1.0 {
+            continue;
+        }
+        let Some(animation) = frame.textures.sprite(&sprite.kind) else {
+            continue;
         };
-        
-        if is_side {
-            col = Color::new(
-                (col.r as f32 * 0.7) as u8,
-                (col.g as f32 * 0.7) as u8,
-                (col.b as f32 * 0.7) as u8,
-                255,
-            );
-        }
-
-        let max_distance = block_size as f32 * 15.0;
-        let fade = (1.0 - (distance / max_distance)).clamp(0.3, 1.0);
-        col = Color::new(
-            (col.r as f32 * fade) as u8,
-            (col.g as f32 * fade) as u8,
-            (col.b as f32 * fade) as u8,
-            255,
-        );
+        // Desfase por posición para que sprites iguales no se animen al unísono
+        let phase = (sprite.x + sprite.y * 7.0) as f64 * 0.01;
+        let texture = animation.frame(time + phase);
 
-        if invulnerability_time > 0.0 && ((time * 10.0) as i32 % 2 == 0) {
-            col = Color::new(
-                (col.r as f32 * 0.5) as u8,
-                (col.g as f32 * 0.5) as u8,
-                (col.b as f32 * 0.5) as u8,
-                255,
-            );
+        // Ángulo relativo al centro de la vista, en [-PI, PI)
+        let mut relative = (sprite.y - player.y).atan2(sprite.x - player.x) - player.angle;
+        relative = (relative + PI).rem_euclid(2.0 * PI) - PI;
+        if relative.abs() > fov {
+            continue;
         }
+        let screen_x = (relative + fov / 2.0) / fov * screen_width;
+
+        // Misma escala que las paredes: en vertical `screen_height / distancia` píxeles por unidad
+        // y en horizontal `screen_width / (fov * distancia)`, porque las columnas reparten el ángulo
+        let world_height = sprite.scale * block_size;
+        let world_width = world_height * texture.width as f32 / texture.height as f32;
+        let height = screen_height * world_height / distance;
+        let width = screen_width * world_width / (fov * distance);
+        // Se apoyan en el suelo de su celda
+        let base = floor_height(frame.maze, sprite.x, sprite.y, frame.block_size);
+        let floor_y = screen_height / 2.0 + screen_height * block_size * (frame.eye - base) / distance;
 
-        // Dibujar línea vertical de la pared
-        framebuffer.set_current_color(col);
-        framebuffer.draw_vertical_line(x as u32, wall_top.max(0), wall_bottom.min(screen_height));
+        let mut factor = distance_fade(distance, frame.block_size);
+        if frame.flash {
+            factor *= 0.5;
+        }
+
+        projected.push(ProjectedSprite {
+            texture,
+            left: screen_x - width / 2.0,
+            top: floor_y - height,
+            width,
+            height,
+            floor_y,
+            distance,
+            factor,
+        });
     }
+
+    // De más lejano a más cercano para que los cercanos tapen a los lejanos
+    projected.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+    projected
 }
 
-fn cast_ray(start_x: f32, start_y: f32, angle: f32, block_size: i32) -> (f32, char, bool) {
-    let dx = angle.cos();
-    let dy = angle.sin();
+// Rellena las filas `first_row..` de una franja: techo y suelo de fondo, lo que ha encontrado el
+// rayo de cada columna y sprites
+fn shade_strip(frame: &Frame, columns: &[Column], sprites: &[ProjectedSprite], first_row: usize, strip: &mut [Color]) {
+    let width = frame.screen_width as usize;
+    let block_size = frame.block_size as f32;
+    let player = frame.player;
+    let horizon = frame.screen_height as f32 / 2.0;
 
-    let mut map_x = (start_x / block_size as f32) as i32;
-    let mut map_y = (start_y / block_size as f32) as i32;
+    for (row_offset, row) in strip.chunks_mut(width).enumerate() {
+        let y = (first_row + row_offset) as i32;
+        let is_floor = y as f32 >= horizon;
+        let row_distance = plane_distance(frame, if is_floor { 0.0 } else { CEILING_HEIGHT }, y);
+        let mut row_factor = distance_fade(row_distance, frame.block_size);
+        if frame.flash {
+            row_factor *= 0.5;
+        }
 
-    let mut side_dist_x: f32;
-    let mut side_dist_y: f32;
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let column = &columns[x];
+            if y >= column.top && y < column.bottom {
+                continue;
+            }
+            let (dir_x, dir_y) = frame.ray_dirs[x];
+            let world_x = player.x + dir_x * row_distance;
+            let world_y = player.y + dir_y * row_distance;
+            let cell = frame.maze.get_maze_cell(world_x, world_y, frame.block_size);
+            let texture = if is_floor { frame.textures.floor(cell) } else { frame.textures.ceiling(cell) };
+            let u = (world_x / block_size).rem_euclid(1.0);
+            let v = (world_y / block_size).rem_euclid(1.0);
+            *pixel = shade(texture.sample(u, v), row_factor);
+        }
+    }
 
-    let delta_dist_x = if dx == 0.0 { 1e30 } else { (1.0 / dx).abs() };
-    let delta_dist_y = if dy == 0.0 { 1e30 } else { (1.0 / dy).abs() };
+    // Cada columna de atrás adelante: lo cercano se pinta encima de lo lejano
+    let rows = first_row as i32..(first_row + strip.len() / width) as i32;
+    for (x, column) in columns.iter().enumerate() {
+        for slice in column.slices.iter().rev() {
+            for y in slice.top.max(rows.start)..slice.bottom.min(rows.end) {
+                strip[(y as usize - first_row) * width + x] = shade_slice(frame, slice, y);
+            }
+        }
+    }
 
-    let mut hit = false;
-    let mut side = false;
+    for (row_offset, row) in strip.chunks_mut(width).enumerate() {
+        let y = (first_row + row_offset) as i32;
+        for sprite in sprites {
+            let y = y as f32;
+            if y < sprite.top || y >= sprite.floor_y {
+                continue;
+            }
+            let v = (y - sprite.top) / sprite.height;
+            let x_start = sprite.left.max(0.0) as usize;
+            let x_end = (sprite.left + sprite.width).min(width as f32).max(0.0) as usize;
+            for x in x_start..x_end {
+                if columns[x].hides(frame, y as i32, sprite.distance) {
+                    continue;
+                }
+                let u = (x as f32 - sprite.left) / sprite.width;
+                let color = sprite.texture.sample(u, v);
+                // Transparencia por recorte: los píxeles casi transparentes no se dibujan
+                if color.a < 128 {
+                    continue;
+                }
+                row[x] = shade(color, sprite.factor);
+            }
+        }
+    }
+}
 
-    let step_x: i32;
-    let step_y: i32;
+// Color de un trozo de columna en la fila `y`
+fn shade_slice(frame: &Frame, slice: &Slice, y: i32) -> Color {
+    match slice.surface {
+        Surface::Face { cell, step, wall_x, texture_top, block, factor, .. } => {
+            // Textura muestreada en el punto exacto del impacto
+            let texture = if step { Some(frame.textures.floor(cell)) } else { frame.textures.wall(cell) };
+            let color = match texture {
+                Some(texture) => {
+                    let v = ((y - texture_top) as f32 / block.max(1) as f32).rem_euclid(1.0);
+                    texture.sample(wall_x, v)
+                }
+                None if cell == 'E' => frame.exit_color,
+                None => Color::WHITE,
+            };
+            shade(color, factor)
+        }
+        Surface::Top { cell, height, ray } => {
+            // Punto de la cara que se ve en esta fila, siguiendo el tramo de rayo en el que está
+            let distance = plane_distance(frame, height, y);
+            let world_x = ray.x + ray.dx * (distance - ray.travelled);
+            let world_y = ray.y + ray.dy * (distance - ray.travelled);
+            let block_size = frame.block_size as f32;
+            let texture = match frame.textures.wall(cell) {
+                Some(texture) if is_wall_char(cell) => texture,
+                _ => frame.textures.floor(frame.maze.get_maze_cell(world_x, world_y, frame.block_size)),
+            };
+            let u = (world_x / block_size).rem_euclid(1.0);
+            let v = (world_y / block_size).rem_euclid(1.0);
+            let mut factor = distance_fade(distance, frame.block_size);
+            if frame.flash {
+                factor *= 0.5;
+            }
+            shade(texture.sample(u, v), factor)
+        }
+    }
+}
 
-    if dx < 0.0 {
-        step_x = -1;
-        side_dist_x = (start_x / block_size as f32 - map_x as f32) * delta_dist_x;
-    } else {
-        step_x = 1;
-        side_dist_x = (map_x as f32 + 1.0 - start_x / block_size as f32) * delta_dist_x;
+// Atenuación por distancia compartida por paredes, suelo y techo
+fn distance_fade(distance: f32, block_size: i32) -> f32 {
+    let max_distance = block_size as f32 * 15.0;
+    (1.0 - (distance / max_distance)).clamp(0.3, 1.0)
+}
+
+// Distancia en línea recta hasta el primer muro, puerta cerrada o portal, para las líneas de
+// visión: lo que hay al otro lado de un portal no se ve en esa dirección. Los muros bajos no la
+// cortan si quedan por debajo de los ojos, igual que el jugador ve por encima de ellos
+pub fn cast_ray(maze: &Maze, start_x: f32, start_y: f32, angle: f32, block_size: i32) -> f32 {
+    let size = block_size as f32;
+    let (x, y) = (start_x / size, start_y / size);
+    let mut dda = Dda::new(x, y, angle);
+    loop {
+        let entry = dda.advance();
+        let Some((row, col)) = dda.cell(maze) else {
+            break;
+        };
+        if matches!(maze.cell(row, col), '#' | 'A' | 'B' | 'C' | 'D' | 'E') && maze.cell_height(row, col) >= EYE_HEIGHT {
+            break;
+        } else if let Some(door) = maze.door(row, col)
+            && let Some((distance, _)) = door_hit(door, x, y, dda.dx, dda.dy)
+        {
+            return distance * size;
+        } else if maze.teleporter(row, col).is_some_and(|teleporter| teleporter.is_portal()) {
+            return entry * size;
+        }
     }
+    dda.face_distance() * size
+}
 
-    if dy < 0.0 {
-        step_y = -1;
-        side_dist_y = (start_y / block_size as f32 - map_y as f32) * delta_dist_y;
-    } else {
-        step_y = 1;
-        side_dist_y = (map_y as f32 + 1.0 - start_y / block_size as f32) * delta_dist_y;
+// Recorrido DDA por la cuadrícula desde (`x`, `y`), celda a celda, en bloques
+struct Dda {
+    x: f32,
+    y: f32,
+    dx: f32,
+    dy: f32,
+    map_x: i32,
+    map_y: i32,
+    step_x: i32,
+    step_y: i32,
+    side_dist_x: f32,
+    side_dist_y: f32,
+    delta_dist_x: f32,
+    delta_dist_y: f32,
+    // La celda actual se entró por una cara norte o sur
+    side: bool,
+}
+
+impl Dda {
+    fn new(x: f32, y: f32, angle: f32) -> Dda {
+        let dx = angle.cos();
+        let dy = angle.sin();
+        let map_x = x.floor() as i32;
+        let map_y = y.floor() as i32;
+        let delta_dist_x = if dx == 0.0 { 1e30 } else { (1.0 / dx).abs() };
+        let delta_dist_y = if dy == 0.0 { 1e30 } else { (1.0 / dy).abs() };
+        let (step_x, side_dist_x) = if dx < 0.0 {
+            (-1, (x - map_x as f32) * delta_dist_x)
+        } else {
+            (1, (map_x as f32 + 1.0 - x) * delta_dist_x)
+        };
+        let (step_y, side_dist_y) = if dy < 0.0 {
+            (-1, (y - map_y as f32) * delta_dist_y)
+        } else {
+            (1, (map_y as f32 + 1.0 - y) * delta_dist_y)
+        };
+        Dda { x, y, dx, dy, map_x, map_y, step_x, step_y, side_dist_x, side_dist_y, delta_dist_x, delta_dist_y, side: false }
     }
 
-    while !hit {
-        if side_dist_x < side_dist_y {
-            side_dist_x += delta_dist_x;
-            map_x += step_x;
-            side = false;
+    // Pasa a la celda siguiente y devuelve la distancia a la que entra en ella
+    fn advance(&mut self) -> f32 {
+        if self.side_dist_x < self.side_dist_y {
+            self.side_dist_x += self.delta_dist_x;
+            self.map_x += self.step_x;
+            self.side = false;
         } else {
-            side_dist_y += delta_dist_y;
-            map_y += step_y;
-            side = true;
+            self.side_dist_y += self.delta_dist_y;
+            self.map_y += self.step_y;
+            self.side = true;
         }
+        self.face_distance()
+    }
+
+    // Corte con la cara por la que se entró en la celda actual, a `distance` píxeles
+    fn hit(&self, distance: f32) -> Hit {
+        Hit { distance, wall_x: self.wall_x(), side: self.side }
+    }
 
-        if map_x < 0 || map_y < 0 || map_x >= MAZE[0].len() as i32 || map_y >= MAZE.len() as i32 {
-            hit = true;
+    // Celda actual, o `None` si el rayo ha salido del mapa
+    fn cell(&self, maze: &Maze) -> Option<(usize, usize)> {
+        let inside = self.map_x >= 0 && self.map_y >= 0 && self.map_x < maze.width() as i32 && self.map_y < maze.height() as i32;
+        inside.then_some((self.map_y as usize, self.map_x as usize))
+    }
+
+    // Distancia a la cara por la que se entró en la celda actual
+    fn face_distance(&self) -> f32 {
+        if !self.side {
+            (self.map_x as f32 - self.x + (1.0 - self.step_x as f32) / 2.0) / self.dx
         } else {
-            let cell = MAZE[map_y as usize].chars().nth(map_x as usize).unwrap_or('#');
-            if matches!(cell, '#' | 'A' | 'B' | 'C' | 'D' | 'E') {
-                hit = true;
-            }
+            (self.map_y as f32 - self.y + (1.0 - self.step_y as f32) / 2.0) / self.dy
         }
     }
 
-    let perp_wall_dist = if !side {
-        (map_x as f32 - start_x / block_size as f32 + (1.0 - step_x as f32) / 2.0) / dx
+    // Punto exacto del impacto a lo largo de esa cara, orientado para que la textura no salga espejada
+    fn wall_x(&self) -> f32 {
+        let distance = self.face_distance();
+        let mut wall_x = if !self.side { self.y + distance * self.dy } else { self.x + distance * self.dx };
+        wall_x -= wall_x.floor();
+        if (!self.side && self.dx > 0.0) || (self.side && self.dy < 0.0) {
+            wall_x = 1.0 - wall_x;
+        }
+        wall_x
+    }
+}
+
+// Impacto con una puerta empotrada en el centro de su celda: distancia en bloques y coordenada
+// de la textura. La puerta se desliza a lo largo de su plano y, por la parte que ya se ha
+// metido en el muro, el rayo sigue de largo
+fn door_hit(door: &Door, x: f32, y: f32, dx: f32, dy: f32) -> Option<(f32, f32)> {
+    let (row, col) = (door.row as f32, door.col as f32);
+    let (distance, along) = if door.vertical {
+        let distance = (col + 0.5 - x) / dx;
+        (distance, y + distance * dy - row)
     } else {
-        (map_y as f32 - start_y / block_size as f32 + (1.0 - step_y as f32) / 2.0) / dy
+        let distance = (row + 0.5 - y) / dy;
+        (distance, x + distance * dx - col)
     };
+    // Con el rayo paralelo al plano la distancia no es finita; detrás del jugador es negativa
+    if !distance.is_finite() || distance <= 0.0 || !(door.open..1.0).contains(&along) {
+        return None;
+    }
+    Some((distance, along - door.open))
+}
 
-    let distance = perp_wall_dist * block_size as f32;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let wall_type = if map_x < 0 || map_y < 0 || map_x >= MAZE[0].len() as i32 || map_y >= MAZE.len() as i32 {
-        '#'
-    } else {
-        MAZE[map_y as usize].chars().nth(map_x as usize).unwrap_or('#')
-    };
+    const BLOCK: i32 = 64;
+
+    fn render(maze: &Maze, textures: &TextureSet, player: &Player, threads: usize) -> Framebuffer {
+        let (width, height) = (160, 120);
+        let mut framebuffer = Framebuffer::new(width as u32, height as u32);
+        let sprites = Sprite::from_decorations(maze, BLOCK);
+        let view = SceneView {
+            fov: PI / 3.0,
+            block_size: BLOCK,
+            time: 1.5,
+            invulnerability_time: 0.0,
+            screen_width: width,
+            screen_height: height,
+            threads,
+        };
+        render_scene(&mut framebuffer, maze, textures, &sprites, player, view);
+        framebuffer
+    }
 
-    (distance, wall_type, side)
-}
\ No newline at end of file
+    #[test]
+    fn threaded_render_matches_single_thread() {
+        let textures = TextureSet::load("assets/textures");
+        // Niveles con portales, alturas y sprites, desde el inicio y desde otra celda
+        for (path, cell) in [("assets/levels/portales.txt", (3, 10)), ("assets/levels/desniveles.txt", (8, 12))] {
+            let maze = Maze::load(path).unwrap();
+            for (row, col) in [maze.start, cell] {
+                let mut player = Player::new((col as f32 + 0.5) * BLOCK as f32, (row as f32 + 0.5) * BLOCK as f32);
+                // Un poco girado para que los rayos no vayan alineados con la cuadrícula
+                player.angle = maze.start_angle + 0.3;
+                let single = render(&maze, &textures, &player, 1);
+                assert!(single.pixels.iter().any(|pixel| *pixel != single.pixels[0]), "imagen vacía");
+                let threaded = render(&maze, &textures, &player, 4);
+                assert_eq!(single.count_differences(&threaded, 0), Some(0), "{} en ({}, {})", path, row, col);
+            }
+        }
+    }
+}
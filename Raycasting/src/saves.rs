@@ -0,0 +1,527 @@
+use std::fs;
+use std::path::Path;
+use crate::cli::LevelSource;
+use crate::doors::KeyColor;
+use crate::game::Session;
+use crate::generator::Algorithm;
+use crate::maze::Maze;
+use crate::pathfinding::{cell_at, Cell};
+use crate::settings::parse_bool;
+
+// Versión del formato; al cambiarlo se sube y `parse` rellena lo que falte en las partidas
+// de versiones anteriores con el estado inicial del nivel.
+//   1: primera versión
+//   2: puertas abiertas, llaves recogidas e inventario
+//   3: posición de los enemigos
+//   4: objetos recogidos y aumento de velocidad
+pub const SAVE_VERSION: u32 = 4;
+
+// Ranuras del menú de guardar/cargar
+pub const SAVE_SLOTS: usize = 3;
+
+// Se guardan en el directorio de trabajo, como los ajustes: `partida_1.txt`, `partida_2.txt`...
+pub fn slot_path(slot: usize) -> String {
+    format!("partida_{}.txt", slot + 1)
+}
+
+// Formato del archivo (`clave = valor` como los ajustes y, tras `---`, las celdas exploradas
+// con `x` y las que no con `.`):
+//
+//   version = 4
+//   nivel = assets/levels/pasillos.txt      (o `generado backtracker 2024 21x15`)
+//   nombre = Pasillos en zigzag
+//   posicion = 96.00 160.50
+//   salud = 2.50
+//   puertas_abiertas = 3 5, 7 9
+//   enemigos = 320.00 96.00 1, 480.50 224.00 0      (x y punto de la ruta, uno por enemigo)
+//   ...
+//   ---
+//   xxx.....
+//
+// Lo que falte se queda como al empezar el nivel.
+#[derive(Debug, Clone)]
+pub struct SaveGame {
+    pub version: u32,
+    pub level: LevelSource,
+    // Nombre del nivel, para mostrarlo en las ranuras sin cargarlo
+    pub name: String,
+    pub position: Option<(f32, f32)>,
+    pub angle: Option<f32>,
+    pub health: Option<f32>,
+    pub max_health: Option<f32>,
+    pub time: f64,
+    pub damage_effect_time: f64,
+    pub invulnerability_time: f64,
+    pub speed_boost_time: f64,
+    pub last_bump_time: f64,
+    pub distance: f32,
+    pub collected: u32,
+    pub show_hint: bool,
+    pub show_map: bool,
+    pub explored: Option<Vec<Vec<bool>>>,
+    pub open_doors: Vec<Cell>,
+    // Llaves del nivel que ya no están en el suelo
+    pub picked_keys: Vec<Cell>,
+    pub inventory: Vec<KeyColor>,
+    // Objetos del nivel que ya no están en el suelo
+    pub picked_pickups: Vec<Cell>,
+    // Posición de cada enemigo y el punto de su ruta hacia el que iba, en el orden del nivel
+    pub enemies: Vec<(f32, f32, usize)>,
+}
+
+impl SaveGame {
+    pub fn from_session(session: &Session, maze: &Maze, level: &LevelSource) -> SaveGame {
+        let player = &session.player;
+        SaveGame {
+            version: SAVE_VERSION,
+            level: level.clone(),
+            name: maze.name.clone(),
+            position: Some((player.x, player.y)),
+            angle: Some(player.angle),
+            health: Some(player.health),
+            max_health: Some(player.max_health),
+            time: session.time,
+            damage_effect_time: session.damage_effect_time,
+            invulnerability_time: session.invulnerability_time,
+            speed_boost_time: session.speed_boost_time,
+            last_bump_time: session.last_bump_time,
+            distance: session.distance,
+            collected: session.collected,
+            show_hint: session.show_hint,
+            show_map: session.show_map,
+            explored: Some(session.explored.clone()),
+            open_doors: maze.doors.iter().filter(|door| door.opening).map(|door| (door.row, door.col)).collect(),
+            picked_keys: maze
+                .keys
+                .iter()
+                .filter(|key| !session.keys.contains(key))
+                .map(|key| (key.row, key.col))
+                .collect(),
+            inventory: player.inventory.keys.clone(),
+            picked_pickups: maze
+                .pickups
+                .iter()
+                .filter(|pickup| !session.pickups.contains(pickup))
+                .map(|pickup| (pickup.row, pickup.col))
+                .collect(),
+            enemies: session.enemies.iter().map(|enemy| (enemy.x, enemy.y, enemy.waypoint)).collect(),
+        }
+    }
+
+    // Aplica la partida a una sesión recién reiniciada en su nivel. Si el nivel cambió desde
+    // que se guardó, lo que ya no encaja se descarta y se avisa
+    pub fn apply(&self, session: &mut Session, maze: &mut Maze, block_size: i32) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // Antes de la versión 2 no se guardaban las puertas: se abren todas para no dejar al
+        // jugador encerrado en una zona que ya había recorrido
+        if self.version < 2 {
+            maze.doors.iter_mut().for_each(|door| door.set_open(true));
+        }
+        for &(row, col) in &self.open_doors {
+            match maze.door_mut(row, col) {
+                Some(door) => door.set_open(true),
+                None => warnings.push(format!("no hay ninguna puerta en ({}, {})", row, col)),
+            }
+        }
+        session.keys.retain(|key| !self.picked_keys.contains(&(key.row, key.col)));
+        session.pickups.retain(|pickup| !self.picked_pickups.contains(&(pickup.row, pickup.col)));
+
+        // Los enemigos solo se recolocan si el nivel sigue teniendo los mismos; vuelven a patrullar
+        // hasta que vean al jugador
+        if !self.enemies.is_empty() {
+            if self.enemies.len() == session.enemies.len() {
+                for (enemy, &(x, y, waypoint)) in session.enemies.iter_mut().zip(&self.enemies) {
+                    let (row, col) = ((y / block_size as f32) as usize, (x / block_size as f32) as usize);
+                    if x >= 0.0 && y >= 0.0 && !maze.is_wall_cell(row, col) {
+                        enemy.x = x;
+                        enemy.y = y;
+                        enemy.waypoint = waypoint % enemy.patrol.len();
+                    }
+                }
+            } else {
+                warnings.push("los enemigos guardados no coinciden con el nivel; empiezan en su sitio".to_string());
+            }
+        }
+
+        let player = &mut session.player;
+        for &key in &self.inventory {
+            player.inventory.add_key(key);
+        }
+        if let Some((x, y)) = self.position {
+            let (row, col) = ((y / block_size as f32) as usize, (x / block_size as f32) as usize);
+            if x >= 0.0 && y >= 0.0 && !maze.is_wall_cell(row, col) {
+                player.x = x;
+                player.y = y;
+                // Si se guardó sobre un teletransporte no se dispara al cargar
+                session.teleport_lock = Some(cell_at(x, y, block_size));
+            } else {
+                warnings.push("la posición guardada cae dentro de un muro; se empieza en el inicio".to_string());
+            }
+        }
+        if let Some(angle) = self.angle {
+            player.angle = angle;
+        }
+        if let Some(max_health) = self.max_health {
+            player.max_health = max_health;
+        }
+        if let Some(health) = self.health {
+            player.health = health.min(player.max_health);
+        }
+        session.time = self.time;
+        session.damage_effect_time = self.damage_effect_time;
+        session.invulnerability_time = self.invulnerability_time;
+        session.speed_boost_time = self.speed_boost_time;
+        session.last_bump_time = self.last_bump_time;
+        session.distance = self.distance;
+        session.collected = self.collected;
+        session.show_hint = self.show_hint;
+        session.show_map = self.show_map;
+        if let Some(explored) = &self.explored {
+            let same_shape = explored.len() == session.explored.len()
+                && explored.iter().zip(&session.explored).all(|(saved, current)| saved.len() == current.len());
+            if same_shape {
+                session.explored = explored.clone();
+            } else {
+                warnings.push("el mapa explorado no coincide con el nivel; se descarta".to_string());
+            }
+        }
+        warnings
+    }
+
+    pub fn load(path: &str) -> Result<SaveGame, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let (save, warnings) = SaveGame::parse(&text).map_err(|err| format!("{}: {}", path, err))?;
+        for warning in warnings {
+            eprintln!("{}: {}", path, warning);
+        }
+        Ok(save)
+    }
+
+    // Una partida sin `nivel` o de una versión más nueva no se puede cargar; cualquier otro
+    // problema se avisa y esa clave se queda como al empezar el nivel
+    pub fn parse(text: &str) -> Result<(SaveGame, Vec<String>), String> {
+        let mut warnings = Vec::new();
+        let mut save = SaveGame {
+            // Las partidas sin `version` son anteriores a que existiera
+            version: 0,
+            level: LevelSource::File(String::new()),
+            name: String::new(),
+            position: None,
+            angle: None,
+            health: None,
+            max_health: None,
+            time: 0.0,
+            damage_effect_time: 0.0,
+            invulnerability_time: 0.0,
+            speed_boost_time: 0.0,
+            last_bump_time: 0.0,
+            distance: 0.0,
+            collected: 0,
+            show_hint: false,
+            show_map: true,
+            explored: None,
+            open_doors: Vec::new(),
+            picked_keys: Vec::new(),
+            inventory: Vec::new(),
+            picked_pickups: Vec::new(),
+            enemies: Vec::new(),
+        };
+        let mut has_level = false;
+        let mut explored: Option<Vec<Vec<bool>>> = None;
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            if let Some(rows) = &mut explored {
+                if !line.is_empty() {
+                    rows.push(line.chars().map(|cell| cell == 'x').collect());
+                }
+                continue;
+            }
+            if line == "---" {
+                explored = Some(Vec::new());
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warnings.push(format!("línea {}: se esperaba `clave = valor`", line_number));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match save.set(key, value) {
+                Ok(true) => has_level = true,
+                Ok(false) => {}
+                Err(message) => warnings.push(format!("línea {}: {}", line_number, message)),
+            }
+        }
+
+        if save.version > SAVE_VERSION {
+            return Err(format!("partida de una versión más nueva del juego ({}; se admite hasta la {})", save.version, SAVE_VERSION));
+        }
+        if !has_level {
+            return Err("la partida no indica el nivel (`nivel`)".to_string());
+        }
+        save.explored = explored;
+        Ok((save, warnings))
+    }
+
+    // Devuelve si la clave era el nivel
+    fn set(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        let invalid = || format!("valor inválido `{}` para `{}`", value, key);
+        match key {
+            "version" => self.version = value.parse().map_err(|_| invalid())?,
+            "nivel" => {
+                self.level = parse_level(value).ok_or_else(invalid)?;
+                return Ok(true);
+            }
+            "nombre" => self.name = value.to_string(),
+            "posicion" => self.position = Some(parse_pair(value).ok_or_else(invalid)?),
+            "angulo" => self.angle = Some(value.parse().map_err(|_| invalid())?),
+            "salud" => self.health = Some(value.parse().map_err(|_| invalid())?),
+            "salud_maxima" => self.max_health = Some(value.parse().ok().filter(|health| *health > 0.0).ok_or_else(invalid)?),
+            "tiempo" => self.time = value.parse().map_err(|_| invalid())?,
+            "efecto_danio" => self.damage_effect_time = value.parse().map_err(|_| invalid())?,
+            "invulnerabilidad" => self.invulnerability_time = value.parse().map_err(|_| invalid())?,
+            "velocidad" => self.speed_boost_time = value.parse().map_err(|_| invalid())?,
+            "ultimo_choque" => self.last_bump_time = value.parse().map_err(|_| invalid())?,
+            "distancia" => self.distance = value.parse().map_err(|_| invalid())?,
+            "recogidos" => self.collected = value.parse().map_err(|_| invalid())?,
+            "pista" => self.show_hint = parse_bool(value).ok_or_else(invalid)?,
+            "mapa" => self.show_map = parse_bool(value).ok_or_else(invalid)?,
+            "puertas_abiertas" => self.open_doors = parse_cells(value).ok_or_else(invalid)?,
+            "llaves_recogidas" => self.picked_keys = parse_cells(value).ok_or_else(invalid)?,
+            "objetos_recogidos" => self.picked_pickups = parse_cells(value).ok_or_else(invalid)?,
+            "inventario" => {
+                self.inventory = value.split_whitespace().map(KeyColor::from_name).collect::<Option<_>>().ok_or_else(invalid)?;
+            }
+            "enemigos" => self.enemies = parse_enemies(value).ok_or_else(invalid)?,
+            _ => return Err(format!("clave desconocida `{}`", key)),
+        }
+        Ok(false)
+    }
+
+    pub fn to_save_string(&self) -> String {
+        let mut text = String::from("# Partida guardada de Laberinto Raycasting\n");
+        text += &format!("version = {}\n", SAVE_VERSION);
+        text += &format!("nivel = {}\n", level_string(&self.level));
+        text += &format!("nombre = {}\n", self.name);
+        if let Some((x, y)) = self.position {
+            text += &format!("posicion = {:.2} {:.2}\n", x, y);
+        }
+        if let Some(angle) = self.angle {
+            text += &format!("angulo = {:.4}\n", angle);
+        }
+        if let Some(health) = self.health {
+            text += &format!("salud = {:.2}\n", health);
+        }
+        if let Some(max_health) = self.max_health {
+            text += &format!("salud_maxima = {:.2}\n", max_health);
+        }
+        text += &format!("tiempo = {:.3}\n", self.time);
+        text += &format!("efecto_danio = {:.3}\n", self.damage_effect_time);
+        text += &format!("invulnerabilidad = {:.3}\n", self.invulnerability_time);
+        text += &format!("velocidad = {:.3}\n", self.speed_boost_time);
+        text += &format!("ultimo_choque = {:.3}\n", self.last_bump_time);
+        text += &format!("distancia = {:.1}\n", self.distance);
+        text += &format!("recogidos = {}\n", self.collected);
+        text += &format!("pista = {}\n", if self.show_hint { "si" } else { "no" });
+        text += &format!("mapa = {}\n", if self.show_map { "si" } else { "no" });
+        text += &format!("puertas_abiertas = {}\n", cells_string(&self.open_doors));
+        text += &format!("llaves_recogidas = {}\n", cells_string(&self.picked_keys));
+        text += &format!("objetos_recogidos = {}\n", cells_string(&self.picked_pickups));
+        let inventory: Vec<&str> = self.inventory.iter().map(|key| key.name()).collect();
+        text += &format!("inventario = {}\n", inventory.join(" "));
+        let enemies: Vec<String> = self.enemies.iter().map(|(x, y, waypoint)| format!("{:.2} {:.2} {}", x, y, waypoint)).collect();
+        text += &format!("enemigos = {}\n", enemies.join(", "));
+        if let Some(explored) = &self.explored {
+            text += "---\n";
+            for row in explored {
+                text.extend(row.iter().map(|&seen| if seen { 'x' } else { '.' }));
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_save_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // Resumen para el menú de ranuras
+    pub fn summary(&self) -> String {
+        let health = match (self.health, self.max_health) {
+            (Some(health), Some(max_health)) => format!(" - salud {:.1}/{:.0}", health, max_health),
+            _ => String::new(),
+        };
+        format!("{} - {:.1} s{}", self.name, self.time, health)
+    }
+}
+
+// Texto de una ranura: vacía, su resumen o por qué no se puede cargar
+pub fn slot_label(slot: usize) -> String {
+    let path = slot_path(slot);
+    if !Path::new(&path).exists() {
+        return format!("Ranura {}: vacía", slot + 1);
+    }
+    match SaveGame::load(&path) {
+        Ok(save) => format!("Ranura {}: {}", slot + 1, save.summary()),
+        Err(_) => format!("Ranura {}: no se puede cargar", slot + 1),
+    }
+}
+
+fn level_string(level: &LevelSource) -> String {
+    match level {
+        LevelSource::File(path) => path.clone(),
+        LevelSource::Generated { algorithm, seed, width, height } => {
+            format!("generado {} {} {}x{}", algorithm.name(), seed, width, height)
+        }
+    }
+}
+
+fn parse_level(value: &str) -> Option<LevelSource> {
+    let Some(generated) = value.strip_prefix("generado ") else {
+        return Some(LevelSource::File(value.to_string())).filter(|_| !value.is_empty());
+    };
+    let mut parts = generated.split_whitespace();
+    let algorithm = Algorithm::from_name(parts.next()?)?;
+    let seed = parts.next()?.parse().ok()?;
+    let (width, height) = parts.next()?.split_once('x')?;
+    Some(LevelSource::Generated {
+        algorithm,
+        seed,
+        width: width.parse().ok()?,
+        height: height.parse().ok()?,
+    })
+}
+
+// Celdas `fila columna` separadas por comas
+fn cells_string(cells: &[Cell]) -> String {
+    cells.iter().map(|(row, col)| format!("{} {}", row, col)).collect::<Vec<_>>().join(", ")
+}
+
+fn parse_cells(value: &str) -> Option<Vec<Cell>> {
+    value
+        .split(',')
+        .filter(|cell| !cell.trim().is_empty())
+        .map(|cell| {
+            let (row, col) = cell.trim().split_once(char::is_whitespace)?;
+            Some((row.parse().ok()?, col.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+// Enemigos `x y punto` separados por comas
+fn parse_enemies(value: &str) -> Option<Vec<(f32, f32, usize)>> {
+    value
+        .split(',')
+        .filter(|enemy| !enemy.trim().is_empty())
+        .map(|enemy| {
+            let mut parts = enemy.split_whitespace();
+            let enemy = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+            parts.next().is_none().then_some(enemy)
+        })
+        .collect()
+}
+
+fn parse_pair(value: &str) -> Option<(f32, f32)> {
+    let (x, y) = value.split_once(char::is_whitespace)?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::damage::Damage;
+    use crate::settings::Settings;
+
+    const BLOCK_SIZE: i32 = 64;
+
+    // Una puerta, una llave y un enemigo, para que la partida tenga de todo
+    const LEVEL: &str = "\
+nombre = Guardado
+inicio = 1 1
+salida = 3 6
+llave = 1 4 roja
+enemigo = 3 1, 3 4
+---
+#######
+#     #
+###P###
+#     E
+#######
+";
+
+    fn new_session() -> (Maze, Session) {
+        let maze = Maze::parse(LEVEL).unwrap();
+        let session = Session::new(&maze, BLOCK_SIZE, Damage::resolve(&Settings::default(), &maze));
+        (maze, session)
+    }
+
+    fn load(text: &str) -> (SaveGame, Maze, Session, Vec<String>) {
+        let (save, parse_warnings) = SaveGame::parse(text).unwrap();
+        assert!(parse_warnings.is_empty(), "{:?}", parse_warnings);
+        let (mut maze, mut session) = new_session();
+        let warnings = save.apply(&mut session, &mut maze, BLOCK_SIZE);
+        (save, maze, session, warnings)
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let (mut maze, mut session) = new_session();
+        maze.door_mut(2, 3).unwrap().set_open(true);
+        session.keys.clear();
+        session.player.inventory.add_key(KeyColor::Red);
+        session.player.x = 3.5 * BLOCK_SIZE as f32;
+        session.player.health = 1.5;
+        session.enemies[0].x = 2.5 * BLOCK_SIZE as f32;
+        session.explored[1][4] = true;
+        session.time = 12.25;
+        let level = LevelSource::File("niveles/guardado.txt".to_string());
+        let text = SaveGame::from_session(&session, &maze, &level).to_save_string();
+
+        let (save, maze, loaded, warnings) = load(&text);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(save.to_save_string(), text);
+        assert!(maze.door(2, 3).unwrap().opening);
+        assert!(loaded.keys.is_empty());
+        assert_eq!(loaded.player.inventory.keys, [KeyColor::Red]);
+        assert_eq!((loaded.player.x, loaded.player.y), (session.player.x, session.player.y));
+        assert_eq!(loaded.player.health, 1.5);
+        assert_eq!(loaded.enemies[0].x, session.enemies[0].x);
+        assert_eq!(loaded.explored, session.explored);
+        assert_eq!(loaded.time, 12.25);
+    }
+
+    #[test]
+    fn version_1_saves_open_every_door() {
+        let (save, maze, session, warnings) = load("version = 1\nnivel = niveles/guardado.txt\nposicion = 224.00 96.00\n");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(save.version, 1);
+        assert!(maze.doors.iter().all(|door| door.opening));
+        // Lo que la versión 1 no guardaba se queda como al empezar el nivel
+        let (_, fresh) = new_session();
+        assert_eq!(session.keys, fresh.keys);
+        assert_eq!((session.enemies[0].x, session.enemies[0].y), (fresh.enemies[0].x, fresh.enemies[0].y));
+        assert_eq!(session.player.x, 224.0);
+    }
+
+    #[test]
+    fn newer_versions_and_missing_levels_are_rejected() {
+        let error = SaveGame::parse(&format!("version = {}\nnivel = niveles/guardado.txt\n", SAVE_VERSION + 1)).unwrap_err();
+        assert!(error.contains("versión más nueva"), "{}", error);
+        let error = SaveGame::parse("version = 4\nposicion = 96.00 96.00\n").unwrap_err();
+        assert!(error.contains("nivel"), "{}", error);
+    }
+
+    #[test]
+    fn explored_map_of_another_shape_is_discarded() {
+        let (_, _, session, warnings) = load("version = 4\nnivel = niveles/guardado.txt\n---\nxxxx\nxxxx\n");
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].contains("mapa explorado"));
+        let (_, fresh) = new_session();
+        assert_eq!(session.explored, fresh.explored);
+    }
+}
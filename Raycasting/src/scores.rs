@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Se guarda en el directorio de trabajo, junto a `ajustes.txt`
+pub const HIGH_SCORES_FILE: &str = "puntuaciones.txt";
+
+// Puestos que se guardan por nivel
+pub const MAX_ENTRIES: usize = 5;
+
+// Puntos máximos por tiempo (terminar en el tiempo par o menos) y por eficiencia del recorrido
+const TIME_POINTS: f32 = 1000.0;
+const EFFICIENCY_POINTS: f32 = 500.0;
+const HEALTH_POINTS: f32 = 100.0;
+const COLLECTIBLE_POINTS: u32 = 50;
+
+// Puntuación de un nivel completado, desglosada para la pantalla de éxito
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Score {
+    // Segundos de partida (sin contar la pausa)
+    pub time: f32,
+    pub time_points: u32,
+    pub health: f32,
+    pub health_points: u32,
+    pub collected: u32,
+    pub collectible_points: u32,
+    // Camino más corto entre lo recorrido, de 0 a 1
+    pub efficiency: f32,
+    pub efficiency_points: u32,
+}
+
+impl Score {
+    // `par` es el tiempo del camino más corto a velocidad máxima (`par_time`) y `distance`
+    // los píxeles recorridos; el camino más corto mide `par * speed`
+    pub fn new(time: f32, par: Option<f32>, health: f32, collected: u32, distance: f32, speed: f32) -> Score {
+        let time_points = match par {
+            Some(par) if time > 0.0 => TIME_POINTS * (par / time).min(1.0),
+            _ => 0.0,
+        };
+        let efficiency = match par {
+            Some(par) if distance > 0.0 => (par * speed / distance).min(1.0),
+            _ => 1.0,
+        };
+        Score {
+            time,
+            time_points: time_points.round() as u32,
+            health,
+            health_points: (health * HEALTH_POINTS).round() as u32,
+            collected,
+            collectible_points: collected * COLLECTIBLE_POINTS,
+            efficiency,
+            efficiency_points: (efficiency * EFFICIENCY_POINTS).round() as u32,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.time_points + self.health_points + self.collectible_points + self.efficiency_points
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    pub points: u32,
+    pub time: f32,
+}
+
+// Formato del archivo:
+//
+//   [Laberinto clásico]
+//   1830 41.2     (puntos segundos, de mejor a peor)
+//   1502 55.0
+//
+// Cada nivel se identifica por su nombre.
+#[derive(Debug, Clone, Default)]
+pub struct HighScores {
+    levels: BTreeMap<String, Vec<Entry>>,
+}
+
+impl HighScores {
+    // Si el archivo no existe la tabla empieza vacía; las líneas inválidas se avisan y se ignoran
+    pub fn load(path: &str) -> HighScores {
+        if !Path::new(path).exists() {
+            return HighScores::default();
+        }
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let (scores, warnings) = HighScores::parse(&text);
+                for warning in warnings {
+                    eprintln!("{}: {}", path, warning);
+                }
+                scores
+            }
+            Err(err) => {
+                eprintln!("No se pudieron leer las puntuaciones de {}: {}", path, err);
+                HighScores::default()
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> (HighScores, Vec<String>) {
+        let mut scores = HighScores::default();
+        let mut warnings = Vec::new();
+        let mut level: Option<String> = None;
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                level = Some(name.trim().to_string());
+                continue;
+            }
+            let Some(name) = &level else {
+                warnings.push(format!("línea {}: puntuación fuera de un `[nivel]`", line_number));
+                continue;
+            };
+            let entry = line.split_once(char::is_whitespace).and_then(|(points, time)| {
+                Some(Entry { points: points.parse().ok()?, time: time.trim().parse().ok()? })
+            });
+            match entry {
+                Some(entry) => {
+                    scores.insert(name, entry);
+                }
+                None => warnings.push(format!("línea {}: se esperaba `puntos segundos`", line_number)),
+            }
+        }
+        (scores, warnings)
+    }
+
+    pub fn to_scores_string(&self) -> String {
+        let mut text = String::from("# Mejores puntuaciones de Laberinto Raycasting\n");
+        for (name, entries) in &self.levels {
+            text += &format!("\n[{}]\n", name);
+            for entry in entries {
+                text += &format!("{} {:.1}\n", entry.points, entry.time);
+            }
+        }
+        text
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_scores_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // Mejores puntuaciones de un nivel, de mejor a peor
+    pub fn level(&self, name: &str) -> &[Entry] {
+        self.levels.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    // Añade la puntuación si entra en la tabla y devuelve su puesto (0 es el primero).
+    // A igualdad de puntos gana el tiempo menor, y la más antigua si también empatan
+    pub fn insert(&mut self, name: &str, entry: Entry) -> Option<usize> {
+        let entries = self.levels.entry(name.to_string()).or_default();
+        let position = entries
+            .iter()
+            .position(|other| entry.points > other.points || (entry.points == other.points && entry.time < other.time))
+            .unwrap_or(entries.len());
+        if position >= MAX_ENTRIES {
+            return None;
+        }
+        entries.insert(position, entry);
+        entries.truncate(MAX_ENTRIES);
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(points: u32, time: f32) -> Entry {
+        Entry { points, time }
+    }
+
+    #[test]
+    fn entries_are_ordered_by_points_then_time() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.insert("nivel", entry(1000, 50.0)), Some(0));
+        assert_eq!(scores.insert("nivel", entry(1500, 60.0)), Some(0));
+        // Mismos puntos en menos tiempo: va delante
+        assert_eq!(scores.insert("nivel", entry(1000, 40.0)), Some(1));
+        // Empate completo: la más antigua se queda delante
+        assert_eq!(scores.insert("nivel", entry(1000, 40.0)), Some(2));
+        assert_eq!(
+            scores.level("nivel"),
+            [entry(1500, 60.0), entry(1000, 40.0), entry(1000, 40.0), entry(1000, 50.0)]
+        );
+        assert!(scores.level("otro").is_empty());
+    }
+
+    #[test]
+    fn tables_keep_only_the_best_entries() {
+        let mut scores = HighScores::default();
+        for points in 1..=MAX_ENTRIES as u32 {
+            scores.insert("nivel", entry(points * 100, 30.0));
+        }
+        // Peor que todas: no entra
+        assert_eq!(scores.insert("nivel", entry(50, 30.0)), None);
+        // Mejor que la última: entra y la última sale
+        assert_eq!(scores.insert("nivel", entry(150, 30.0)), Some(MAX_ENTRIES - 1));
+        let points: Vec<u32> = scores.level("nivel").iter().map(|entry| entry.points).collect();
+        assert_eq!(points, [500, 400, 300, 200, 150]);
+    }
+
+    #[test]
+    fn scores_round_trip_through_the_file_format() {
+        let mut scores = HighScores::default();
+        scores.insert("Laberinto clásico", entry(1830, 41.2));
+        scores.insert("Laberinto clásico", entry(1502, 55.0));
+        scores.insert("Salas", entry(900, 80.5));
+        let (read, warnings) = HighScores::parse(&scores.to_scores_string());
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(read.level("Laberinto clásico"), scores.level("Laberinto clásico"));
+        assert_eq!(read.level("Salas"), scores.level("Salas"));
+    }
+
+    #[test]
+    fn invalid_lines_are_reported() {
+        let (scores, warnings) = HighScores::parse("100 10.0\n[Salas]\nmuchos puntos\n300 20.0\n");
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].starts_with("línea 1:"));
+        assert!(warnings[1].starts_with("línea 3:"));
+        assert_eq!(scores.level("Salas"), [entry(300, 20.0)]);
+    }
+}
@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::Path;
+use crate::damage::DamagePolicy;
+use crate::input::{Action, Bindings, binding_from_name, binding_name};
+
+// Se busca junto al ejecutable, en el directorio de trabajo, como los assets
+pub const SETTINGS_FILE: &str = "ajustes.txt";
+
+// Resoluciones que se pueden elegir desde el menú de ajustes
+pub const RESOLUTIONS: [(i32, i32); 4] = [(960, 540), (1280, 720), (1600, 900), (1920, 1080)];
+
+// Límites de los ajustes numéricos (mínimo, máximo), los mismos en el menú y en el archivo
+pub const FOV_RANGE: (f32, f32) = (45.0, 120.0);
+pub const SENSITIVITY_RANGE: (f32, f32) = (0.0005, 0.02);
+pub const MAX_VOLUME: u32 = 100;
+pub const DEAD_ZONE_RANGE: (f32, f32) = (0.0, 0.9);
+pub const PAD_ROTATION_RANGE: (f32, f32) = (0.5, 6.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "facil",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "dificil",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|difficulty| difficulty.name() == name)
+    }
+
+    pub fn lives(self) -> i32 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 2,
+        }
+    }
+
+    // Segundos de invulnerabilidad tras un choque
+    pub fn invulnerability(self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.6,
+        }
+    }
+
+    // Política de daño si ni los ajustes ni el nivel eligen otra
+    pub fn damage_policy(self) -> DamagePolicy {
+        match self {
+            Difficulty::Easy => DamagePolicy::HealthBar,
+            Difficulty::Normal | Difficulty::Hard => DamagePolicy::Lives,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub screen_width: i32,
+    pub screen_height: i32,
+    // Campo de visión en grados
+    pub fov_degrees: f32,
+    pub mouse_sensitivity: f32,
+    pub invert_mouse: bool,
+    // rad/s al girar con teclado
+    pub key_rotation_speed: f32,
+    // rad/s al girar con el stick a fondo
+    pub pad_rotation_speed: f32,
+    // Volúmenes de 0 a 100
+    pub music_volume: u32,
+    pub sfx_volume: u32,
+    pub bindings: Bindings,
+    // Zona muerta de los sticks del mando, de 0 a 1
+    pub dead_zone: f32,
+    pub difficulty: Difficulty,
+    // `None` (auto): la del nivel o, si no tiene, la de la dificultad
+    pub damage: Option<DamagePolicy>,
+    // En la campaña, pasar al siguiente nivel con la salud que quede en vez de con toda
+    pub carry_lives: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            screen_width: 1280,
+            screen_height: 720,
+            fov_degrees: 60.0,
+            mouse_sensitivity: 0.003,
+            invert_mouse: false,
+            key_rotation_speed: 0.24,
+            pad_rotation_speed: 2.5,
+            music_volume: 100,
+            sfx_volume: 100,
+            bindings: Bindings::default(),
+            dead_zone: 0.2,
+            difficulty: Difficulty::Normal,
+            damage: None,
+            carry_lives: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn fov(&self) -> f32 {
+        self.fov_degrees.to_radians()
+    }
+
+    // Lee los ajustes; si el archivo no existe se usan los valores por defecto,
+    // cada línea inválida se avisa y se ignora, conservando el resto, y los números
+    // fuera de rango se llevan al límite más cercano, también con un aviso
+    pub fn load(path: &str) -> Settings {
+        if !Path::new(path).exists() {
+            return Settings::default();
+        }
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let (settings, warnings) = Settings::parse(&text);
+                for warning in warnings {
+                    eprintln!("{}: {}", path, warning);
+                }
+                settings
+            }
+            Err(err) => {
+                eprintln!("No se pudieron leer los ajustes de {}: {}", path, err);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> (Settings, Vec<String>) {
+        let mut settings = Settings::default();
+        let mut warnings = Vec::new();
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warnings.push(format!("línea {}: se esperaba `clave = valor`", line_number));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if let Err(message) = settings.set(key, value) {
+                warnings.push(format!("línea {}: {}", line_number, message));
+            }
+        }
+        warnings.extend(settings.clamp_to_ranges());
+        (settings, warnings)
+    }
+
+    // Lleva cada número a su rango y devuelve un aviso por cada uno que se salía
+    fn clamp_to_ranges(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut clamp = |key: &str, value: &mut f32, (min, max): (f32, f32)| {
+            if !(min..=max).contains(value) {
+                let clamped = value.clamp(min, max);
+                warnings.push(format!("`{}` = {} fuera de rango, se usa {}", key, value, clamped));
+                *value = clamped;
+            }
+        };
+        clamp("campo_vision", &mut self.fov_degrees, FOV_RANGE);
+        clamp("sensibilidad", &mut self.mouse_sensitivity, SENSITIVITY_RANGE);
+        clamp("zona_muerta", &mut self.dead_zone, DEAD_ZONE_RANGE);
+        clamp("giro_mando", &mut self.pad_rotation_speed, PAD_ROTATION_RANGE);
+        for (key, volume) in [("volumen_musica", &mut self.music_volume), ("volumen_efectos", &mut self.sfx_volume)] {
+            if *volume > MAX_VOLUME {
+                warnings.push(format!("`{}` = {} fuera de rango, se usa {}", key, volume, MAX_VOLUME));
+                *volume = MAX_VOLUME;
+            }
+        }
+        warnings
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("valor inválido `{}` para `{}`", value, key);
+        match key {
+            "resolucion" => {
+                let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+                let width: i32 = width.trim().parse().map_err(|_| invalid())?;
+                let height: i32 = height.trim().parse().map_err(|_| invalid())?;
+                if width < 320 || height < 200 {
+                    return Err(invalid());
+                }
+                self.screen_width = width;
+                self.screen_height = height;
+            }
+            "campo_vision" => self.fov_degrees = parse_number(value).ok_or_else(invalid)?,
+            "sensibilidad" => self.mouse_sensitivity = parse_number(value).ok_or_else(invalid)?,
+            "invertir_raton" => self.invert_mouse = parse_bool(value).ok_or_else(invalid)?,
+            "conservar_vidas" => self.carry_lives = parse_bool(value).ok_or_else(invalid)?,
+            "giro_teclado" => {
+                self.key_rotation_speed = value.parse().ok().filter(|s: &f32| *s > 0.0).ok_or_else(invalid)?;
+            }
+            "giro_mando" => self.pad_rotation_speed = parse_number(value).ok_or_else(invalid)?,
+            "volumen_musica" => self.music_volume = value.parse().map_err(|_| invalid())?,
+            "volumen_efectos" => self.sfx_volume = value.parse().map_err(|_| invalid())?,
+            "zona_muerta" => self.dead_zone = parse_number(value).ok_or_else(invalid)?,
+            "dificultad" => self.difficulty = Difficulty::from_name(value).ok_or_else(invalid)?,
+            "danio" => {
+                self.damage = match value {
+                    "auto" => None,
+                    _ => Some(DamagePolicy::from_name(value).ok_or_else(invalid)?),
+                };
+            }
+            _ => {
+                // `control_<acción> = W, STICK_IZQ_Y-, ...`; también se acepta el antiguo `tecla_<acción>`
+                let action = key
+                    .strip_prefix("control_")
+                    .or_else(|| key.strip_prefix("tecla_"))
+                    .and_then(Action::from_name)
+                    .ok_or_else(|| format!("ajuste desconocido `{}`", key))?;
+                let bindings = value
+                    .split(',')
+                    .filter(|name| !name.trim().is_empty())
+                    .map(|name| binding_from_name(name).ok_or_else(|| format!("control desconocido `{}` en `{}`", name.trim(), key)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.bindings.set(action, bindings);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_settings_string(&self) -> String {
+        let mut text = String::from("# Ajustes de Laberinto Raycasting\n");
+        text += &format!("resolucion = {}x{}\n", self.screen_width, self.screen_height);
+        text += &format!("campo_vision = {}\n", self.fov_degrees);
+        text += &format!("sensibilidad = {}\n", self.mouse_sensitivity);
+        text += &format!("invertir_raton = {}\n", if self.invert_mouse { "si" } else { "no" });
+        text += &format!("giro_teclado = {}\n", self.key_rotation_speed);
+        text += &format!("giro_mando = {}\n", self.pad_rotation_speed);
+        text += &format!("volumen_musica = {}\n", self.music_volume);
+        text += &format!("volumen_efectos = {}\n", self.sfx_volume);
+        text += &format!("zona_muerta = {}\n", self.dead_zone);
+        text += &format!("dificultad = {}\n", self.difficulty.name());
+        text += &format!("danio = {}\n", self.damage.map_or("auto", DamagePolicy::name));
+        text += &format!("conservar_vidas = {}\n", if self.carry_lives { "si" } else { "no" });
+        for action in Action::ALL {
+            let names: Vec<String> = self.bindings.get(action).iter().map(|binding| binding_name(*binding)).collect();
+            text += &format!("control_{} = {}\n", action.name(), names.join(", "));
+        }
+        text
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_settings_string()).map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+// Número finito; el rango se comprueba después, en `clamp_to_ranges`
+fn parse_number(value: &str) -> Option<f32> {
+    value.parse().ok().filter(|number: &f32| number.is_finite())
+}
+
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "si" | "sí" | "true" | "1" => Some(true),
+        "no" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_and_invalid_values_keep_the_defaults() {
+        let (settings, warnings) = Settings::parse("color = rojo\ncampo_vision = ancho\nvolumen_musica = -5\nzona_muerta = 0.3\n");
+        let defaults = Settings::default();
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].starts_with("línea 1:") && warnings[0].contains("ajuste desconocido"));
+        assert!(warnings[1].starts_with("línea 2:") && warnings[1].contains("valor inválido"));
+        assert!(warnings[2].starts_with("línea 3:"));
+        assert_eq!(settings.fov_degrees, defaults.fov_degrees);
+        assert_eq!(settings.music_volume, defaults.music_volume);
+        // Las líneas válidas se aplican igualmente
+        assert_eq!(settings.dead_zone, 0.3);
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_clamped_to_the_menu_limits() {
+        let (settings, warnings) =
+            Settings::parse("campo_vision = 170\nsensibilidad = 0\nvolumen_efectos = 150\nzona_muerta = 0.95\ngiro_mando = 10\n");
+        assert_eq!(warnings.len(), 5, "{:?}", warnings);
+        assert!(warnings.iter().all(|warning| warning.contains("fuera de rango")));
+        assert_eq!(settings.fov_degrees, FOV_RANGE.1);
+        assert_eq!(settings.mouse_sensitivity, SENSITIVITY_RANGE.0);
+        assert_eq!(settings.sfx_volume, MAX_VOLUME);
+        assert_eq!(settings.dead_zone, DEAD_ZONE_RANGE.1);
+        assert_eq!(settings.pad_rotation_speed, PAD_ROTATION_RANGE.1);
+    }
+
+    #[test]
+    fn saved_settings_read_back_without_warnings() {
+        let (settings, warnings) = Settings::parse(&Settings::default().to_settings_string());
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(settings.to_settings_string(), Settings::default().to_settings_string());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::maze::Maze;
+use crate::pathfinding::{cell_at, Cell};
+
+// Teletransportes de un solo sentido. Al pisar una celda `T` se aparece en el centro de su
+// destino mirando hacia el ángulo de salida. Las celdas `O` son portales: a través de ellas se
+// ve el destino y al cruzarlas se conserva la posición dentro de la celda y el giro, así que la
+// vista no salta. Para ir y volver se declaran dos, uno en cada sentido.
+pub const TELEPORTER_CHAR: char = 'T';
+pub const PORTAL_CHAR: char = 'O';
+
+// Al cruzar un portal el punto se acerca un poco al centro para que caiga dentro de la celda de
+// destino y no justo en el borde con su vecina
+const PORTAL_INSET: f32 = 0.99;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Teleporter {
+    pub from: Cell,
+    pub to: Cell,
+    // En radianes
+    pub exit_angle: f32,
+    // Solo en los portales: quien entra mirando hacia aquí sale mirando hacia `exit_angle`, y todo
+    // lo que lo cruza gira la diferencia
+    pub entry_angle: Option<f32>,
+}
+
+impl Teleporter {
+    pub fn is_portal(&self) -> bool {
+        self.entry_angle.is_some()
+    }
+
+    // Posición y ángulo al otro lado; `size` es el lado de la celda en las unidades de `x` e `y`
+    // (píxeles para el jugador, 1 para los rayos, que van en bloques)
+    pub fn transform(&self, x: f32, y: f32, angle: f32, size: f32) -> (f32, f32, f32) {
+        let center = |(row, col): Cell| ((col as f32 + 0.5) * size, (row as f32 + 0.5) * size);
+        let (to_x, to_y) = center(self.to);
+        let Some(entry_angle) = self.entry_angle else {
+            return (to_x, to_y, self.exit_angle);
+        };
+        let rotation = self.exit_angle - entry_angle;
+        let (sin, cos) = rotation.sin_cos();
+        let (from_x, from_y) = center(self.from);
+        let (local_x, local_y) = ((x - from_x) * PORTAL_INSET, (y - from_y) * PORTAL_INSET);
+        (to_x + local_x * cos - local_y * sin, to_y + local_x * sin + local_y * cos, angle + rotation)
+    }
+}
+
+// Cruza el teletransporte de la celda en la que está (`x`, `y`), si hay uno, y devuelve la nueva
+// posición y ángulo. `lock` es la celda a la que se llegó con el último: no se activa hasta salir
+// de ella, para no rebotar entre dos que se apuntan el uno al otro
+pub fn teleport(maze: &Maze, x: f32, y: f32, angle: f32, lock: &mut Option<Cell>, block_size: i32) -> Option<(f32, f32, f32)> {
+    let cell = cell_at(x, y, block_size);
+    if *lock == Some(cell) {
+        return None;
+    }
+    *lock = None;
+    let teleporter = maze.teleporter(cell.0, cell.1)?;
+    let (x, y, angle) = teleporter.transform(x, y, angle, block_size as f32);
+    *lock = Some(cell_at(x, y, block_size));
+    Some((x, y, angle))
+}
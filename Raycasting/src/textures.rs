@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::path::Path;
+use raylib::prelude::{Color, Image};
+use crate::doors::KeyColor;
+use crate::enemies::ENEMY_SPRITE;
+use crate::pickups::PickupKind;
+use crate::maze::{wall_color, DECORATIONS};
+use crate::teleporters::{PORTAL_CHAR, TELEPORTER_CHAR};
+
+pub const TEXTURE_DIR: &str = "assets/textures";
+const TEXTURE_SIZE: usize = 64;
+
+// Archivo de textura para cada letra de muro o puerta (se busca `<nombre>.png` en la carpeta de texturas)
+const WALL_FILES: [(char, &str); 9] = [
+    ('#', "ladrillo"),
+    ('A', "muro_a"),
+    ('B', "muro_b"),
+    ('C', "muro_c"),
+    ('D', "muro_d"),
+    ('P', "puerta"),
+    ('1', "puerta_roja"),
+    ('2', "puerta_verde"),
+    ('3', "puerta_azul"),
+];
+
+// Texturas de suelo y techo por tipo de celda; deben ser repetibles (tileables)
+const FLOOR_FILES: [(char, &str); 3] = [(' ', "suelo"), ('T', "teletransporte"), ('O', "portal")];
+const CEILING_FILES: [(char, &str); 1] = [(' ', "techo")];
+
+// Cuadros por segundo de las animaciones de sprites
+const SPRITE_FPS: f32 = 8.0;
+
+// Textura en memoria de CPU; el renderer la muestrea píxel a píxel
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Texture {
+    pub fn load(path: &str) -> Option<Texture> {
+        if !Path::new(path).exists() {
+            return None;
+        }
+        match Image::load_image(path) {
+            Ok(image) => Some(Texture {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                pixels: image.get_image_data().to_vec(),
+            }),
+            Err(err) => {
+                eprintln!("Error cargando textura {}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    fn generate(base: Color, pattern: impl Fn(usize, usize) -> f32) -> Texture {
+        let mut pixels = Vec::with_capacity(TEXTURE_SIZE * TEXTURE_SIZE);
+        for y in 0..TEXTURE_SIZE {
+            for x in 0..TEXTURE_SIZE {
+                pixels.push(shade(base, pattern(x, y)));
+            }
+        }
+        Texture {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            pixels,
+        }
+    }
+
+    // Ladrillos con juntas oscuras y filas desplazadas
+    pub fn brick(base: Color) -> Texture {
+        Texture::generate(base, |x, y| {
+            let row = y / 16;
+            let offset = if row % 2 == 0 { 0 } else { 16 };
+            let mortar = y % 16 == 0 || (x + offset) % 32 == 0;
+            if mortar {
+                0.45
+            } else {
+                0.85 + 0.15 * noise(x, y)
+            }
+        })
+    }
+
+    // Bloques de piedra grandes con borde biselado
+    pub fn stone(base: Color) -> Texture {
+        Texture::generate(base, |x, y| {
+            let (bx, by) = (x % 32, y % 32);
+            if bx == 0 || by == 0 {
+                0.4
+            } else if bx == 1 || by == 1 {
+                1.1
+            } else {
+                0.8 + 0.2 * noise(x / 2, y / 2)
+            }
+        })
+    }
+
+    pub fn checker(base: Color) -> Texture {
+        Texture::generate(base, |x, y| {
+            if (x / 8 + y / 8) % 2 == 0 { 1.0 } else { 0.7 }
+        })
+    }
+
+    // Paneles verticales
+    pub fn stripes(base: Color) -> Texture {
+        Texture::generate(base, |x, _| {
+            let band = x % 16;
+            if band < 2 { 0.5 } else { 0.9 + 0.1 * ((band as f32) / 16.0) }
+        })
+    }
+
+    // Tablones verticales con marco y dos travesaños
+    pub fn door(base: Color) -> Texture {
+        Texture::generate(base, |x, y| {
+            let frame = x < 3 || y < 3 || x >= TEXTURE_SIZE - 3 || y >= TEXTURE_SIZE - 3;
+            if frame {
+                0.5
+            } else if (12..18).contains(&y) || (46..52).contains(&y) {
+                1.1
+            } else if x % 12 == 0 {
+                0.6
+            } else {
+                0.85 + 0.15 * noise(x, y / 6)
+            }
+        })
+    }
+
+    // Igual que `generate` pero con transparencia: `None` deja el píxel transparente
+    fn generate_sprite(pattern: impl Fn(usize, usize) -> Option<Color>) -> Texture {
+        let mut pixels = Vec::with_capacity(TEXTURE_SIZE * TEXTURE_SIZE);
+        for y in 0..TEXTURE_SIZE {
+            for x in 0..TEXTURE_SIZE {
+                pixels.push(pattern(x, y).unwrap_or(Color::BLANK));
+            }
+        }
+        Texture {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            pixels,
+        }
+    }
+
+    // Muestra la textura en coordenadas normalizadas [0, 1)
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        let tx = ((u * self.width as f32) as usize).min(self.width - 1);
+        let ty = ((v * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[ty * self.width + tx]
+    }
+}
+
+// Secuencia de cuadros de un sprite
+pub struct SpriteAnimation {
+    pub frames: Vec<Texture>,
+    pub fps: f32,
+}
+
+impl SpriteAnimation {
+    pub fn frame(&self, time: f64) -> &Texture {
+        let index = (time * self.fps as f64) as usize % self.frames.len();
+        &self.frames[index]
+    }
+}
+
+pub struct TextureSet {
+    walls: HashMap<char, Texture>,
+    sprites: HashMap<String, SpriteAnimation>,
+    floors: HashMap<char, Texture>,
+    ceilings: HashMap<char, Texture>,
+    default_floor: Texture,
+    default_ceiling: Texture,
+}
+
+impl TextureSet {
+    // Carga las texturas de `dir`; las que no existan se generan proceduralmente
+    pub fn load(dir: &str) -> TextureSet {
+        let load_all = |files: &[(char, &str)], fallback: &dyn Fn(char) -> Texture| {
+            files
+                .iter()
+                .map(|&(cell, name)| {
+                    let path = format!("{}/{}.png", dir, name);
+                    (cell, Texture::load(&path).unwrap_or_else(|| fallback(cell)))
+                })
+                .collect::<HashMap<char, Texture>>()
+        };
+
+        // Sprites: `<tipo>.png` para un único cuadro o `<tipo>_0.png`, `<tipo>_1.png`... para animarlos
+        let mut sprites = HashMap::new();
+        let keys = KeyColor::ALL.map(KeyColor::sprite);
+        let pickups = PickupKind::ALL.map(PickupKind::name);
+        for kind in DECORATIONS.iter().chain(&keys).chain(&pickups).chain(&[ENEMY_SPRITE]) {
+            let mut frames = Vec::new();
+            while let Some(frame) = Texture::load(&format!("{}/{}_{}.png", dir, kind, frames.len())) {
+                frames.push(frame);
+            }
+            if frames.is_empty() {
+                frames = match Texture::load(&format!("{}/{}.png", dir, kind)) {
+                    Some(frame) => vec![frame],
+                    None => procedural_sprite(kind),
+                };
+            }
+            sprites.insert(kind.to_string(), SpriteAnimation { frames, fps: SPRITE_FPS });
+        }
+
+        TextureSet {
+            walls: load_all(&WALL_FILES, &procedural_wall),
+            sprites,
+            floors: load_all(&FLOOR_FILES, &procedural_floor),
+            ceilings: load_all(&CEILING_FILES, &procedural_ceiling),
+            default_floor: procedural_floor(' '),
+            default_ceiling: procedural_ceiling(' '),
+        }
+    }
+
+    pub fn wall(&self, cell: char) -> Option<&Texture> {
+        self.walls.get(&cell)
+    }
+
+    pub fn sprite(&self, kind: &str) -> Option<&SpriteAnimation> {
+        self.sprites.get(kind)
+    }
+
+    pub fn floor(&self, cell: char) -> &Texture {
+        self.floors.get(&cell).unwrap_or(&self.default_floor)
+    }
+
+    pub fn ceiling(&self, cell: char) -> &Texture {
+        self.ceilings.get(&cell).unwrap_or(&self.default_ceiling)
+    }
+}
+
+fn procedural_wall(cell: char) -> Texture {
+    let base = wall_color(cell);
+    match cell {
+        '#' | 'A' => Texture::brick(base),
+        'B' => Texture::stone(base),
+        'C' => Texture::checker(base),
+        'P' | '1' | '2' | '3' => Texture::door(base),
+        _ => Texture::stripes(base),
+    }
+}
+
+// Baldosas grises con junta, en los tonos del suelo original; los teletransportes y portales llevan
+// un anillo luminoso de su color
+fn procedural_floor(cell: char) -> Texture {
+    if cell == TELEPORTER_CHAR || cell == PORTAL_CHAR {
+        let half = TEXTURE_SIZE as f32 / 2.0;
+        return Texture::generate(wall_color(cell), |x, y| {
+            let distance = (x as f32 + 0.5 - half).hypot(y as f32 + 0.5 - half) / half;
+            let ring = 1.0 - ((distance - 0.7).abs() * 8.0).min(1.0);
+            0.3 + 0.2 * noise(x / 4, y / 4) + 0.9 * ring
+        });
+    }
+    Texture::generate(Color::new(180, 180, 180, 255), |x, y| {
+        if x % 32 == 0 || y % 32 == 0 {
+            0.6
+        } else {
+            0.85 + 0.15 * noise(x / 4, y / 4)
+        }
+    })
+}
+
+// Paneles de techo en el azul del cielo original
+fn procedural_ceiling(_cell: char) -> Texture {
+    Texture::generate(Color::new(66, 135, 245, 255), |x, y| {
+        let (px, py) = (x % 32, y % 32);
+        if px < 2 || py < 2 {
+            0.55
+        } else {
+            0.9 + 0.1 * noise(x / 8, y / 8)
+        }
+    })
+}
+
+// Sprites de decoración, llaves, objetos y enemigos dibujados en código; la antorcha tiene cuatro
+// cuadros de llama, la moneda cuatro de giro y el enemigo dos de ondulación
+fn procedural_sprite(kind: &str) -> Vec<Texture> {
+    let size = TEXTURE_SIZE as f32;
+    if let Some(color) = KeyColor::ALL.into_iter().find(|color| color.sprite() == kind) {
+        return vec![procedural_key(color.color())];
+    }
+    if let Some(pickup) = PickupKind::from_name(kind) {
+        return procedural_pickup(pickup);
+    }
+    match kind {
+        ENEMY_SPRITE => (0..2).map(procedural_enemy).collect(),
+        "antorcha" => (0..4)
+            .map(|frame| {
+                Texture::generate_sprite(move |x, y| {
+                    let (fx, fy) = (x as f32 / size, y as f32 / size);
+                    if (0.46..0.54).contains(&fx) && fy > 0.45 {
+                        return Some(Color::new(110, 70, 35, 255));
+                    }
+                    // La llama oscila de lado y de altura según el cuadro
+                    let sway = [0.0, 0.03, 0.0, -0.03][frame];
+                    let height = [0.3, 0.34, 0.28, 0.33][frame];
+                    let dx = (fx - 0.5 - sway) / 0.12;
+                    let dy = (fy - 0.45 + height / 2.0) / (height / 2.0);
+                    let r = dx * dx + dy * dy;
+                    if r < 0.35 {
+                        Some(Color::new(255, 240, 150, 255))
+                    } else if r < 1.0 {
+                        Some(Color::new(255, 140, 30, 255))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect(),
+        "columna" => vec![Texture::generate_sprite(|x, y| {
+            let fx = x as f32 / size;
+            let capital = !(6..TEXTURE_SIZE - 6).contains(&y);
+            let half_width = if capital { 0.3 } else { 0.2 };
+            if (fx - 0.5).abs() < half_width {
+                let light = 1.0 - (fx - 0.4).abs() * 2.0;
+                Some(shade(Color::new(200, 200, 190, 255), light.clamp(0.5, 1.0)))
+            } else {
+                None
+            }
+        })],
+        "planta" => vec![Texture::generate_sprite(|x, y| {
+            let (fx, fy) = (x as f32 / size, y as f32 / size);
+            if fy > 0.75 && (fx - 0.5).abs() < 0.15 + (1.0 - fy) * 0.2 {
+                return Some(Color::new(150, 80, 50, 255));
+            }
+            let dx = (fx - 0.5) / 0.32;
+            let dy = (fy - 0.5) / 0.28;
+            if dx * dx + dy * dy < 1.0 && fy <= 0.75 {
+                Some(shade(Color::new(50, 160, 60, 255), 0.7 + 0.3 * noise(x / 3, y / 3)))
+            } else {
+                None
+            }
+        })],
+        _ => vec![Texture::generate_sprite(|x, y| {
+            let (fx, fy) = (x as f32 / size, y as f32 / size);
+            if fy < 0.45 || (fx - 0.5).abs() > 0.25 - (fy - 0.72).abs() * 0.15 {
+                return None;
+            }
+            let hoop = (fy - 0.55).abs() < 0.02 || (fy - 0.9).abs() < 0.02;
+            if hoop {
+                Some(Color::new(90, 90, 90, 255))
+            } else {
+                Some(shade(Color::new(140, 90, 45, 255), 0.8 + 0.2 * noise(x, y / 8)))
+            }
+        })],
+    }
+}
+
+// Llave tumbada en el suelo: anilla a la izquierda, caña y dos dientes
+fn procedural_key(color: Color) -> Texture {
+    let size = TEXTURE_SIZE as f32;
+    Texture::generate_sprite(move |x, y| {
+        let (fx, fy) = (x as f32 / size, y as f32 / size);
+        let (dx, dy) = ((fx - 0.28) / 0.14, (fy - 0.8) / 0.14);
+        let ring = (0.45..1.0).contains(&(dx * dx + dy * dy));
+        let shaft = (0.4..0.85).contains(&fx) && (fy - 0.8).abs() < 0.035;
+        let teeth = (fy > 0.8 && fy < 0.92) && ((0.66..0.71).contains(&fx) || (0.77..0.83).contains(&fx));
+        if ring || shaft || teeth {
+            Some(shade(color, 0.8 + 0.2 * (1.0 - fy)))
+        } else {
+            None
+        }
+    })
+}
+
+fn procedural_pickup(kind: PickupKind) -> Vec<Texture> {
+    let size = TEXTURE_SIZE as f32;
+    match kind {
+        // La moneda se estrecha y se ensancha como si girara
+        PickupKind::Coin => [1.0, 0.6, 0.15, 0.6]
+            .into_iter()
+            .map(|width| {
+                Texture::generate_sprite(move |x, y| {
+                    let (dx, dy) = ((x as f32 / size - 0.5) / (0.3 * width), (y as f32 / size - 0.6) / 0.3);
+                    let r = dx * dx + dy * dy;
+                    if r < 0.55 {
+                        Some(Color::new(255, 215, 60, 255))
+                    } else if r < 1.0 {
+                        Some(Color::new(200, 150, 20, 255))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect(),
+        // Dos círculos y un triángulo invertido
+        PickupKind::Heart => vec![Texture::generate_sprite(|x, y| {
+            let (fx, fy) = (x as f32 / size, y as f32 / size);
+            let lobe = |cx: f32| (fx - cx).powi(2) + (fy - 0.5).powi(2) < 0.15 * 0.15;
+            let point = (0.5..0.9).contains(&fy) && (fx - 0.5).abs() < 0.3 * (0.9 - fy) / 0.4;
+            (lobe(0.36) || lobe(0.64) || point).then(|| shade(Color::new(230, 30, 60, 255), 1.1 - fy * 0.4))
+        })],
+        // Escudo: rectángulo que se cierra en punta, con el borde más claro
+        PickupKind::Invulnerability => vec![Texture::generate_sprite(|x, y| {
+            let (fx, fy) = (x as f32 / size, y as f32 / size);
+            let half_width = if fy < 0.65 { 0.25 } else { 0.25 * (0.95 - fy) / 0.3 };
+            if !(0.35..0.95).contains(&fy) || (fx - 0.5).abs() > half_width {
+                return None;
+            }
+            let edge = (fx - 0.5).abs() > half_width - 0.05 || fy < 0.4;
+            Some(if edge { Color::new(200, 220, 255, 255) } else { Color::new(60, 110, 220, 255) })
+        })],
+        // Rayo en zigzag
+        PickupKind::Speed => vec![Texture::generate_sprite(|x, y| {
+            let (fx, fy) = (x as f32 / size, y as f32 / size);
+            if !(0.35..0.95).contains(&fy) {
+                return None;
+            }
+            // Dos trazos inclinados unidos por un tramo horizontal
+            let center = if fy < 0.68 { 0.62 - (fy - 0.35) * 0.6 } else { 0.58 - (fy - 0.68) * 0.75 };
+            let joint = (0.62..0.72).contains(&fy) && (0.4..0.62).contains(&fx);
+            ((fx - center).abs() < 0.07 || joint).then_some(Color::new(255, 230, 40, 255))
+        })],
+        // Pergamino con líneas de pasillos
+        PickupKind::Map => vec![Texture::generate_sprite(|x, y| {
+            let (fx, fy) = (x as f32 / size, y as f32 / size);
+            if !(0.5..0.95).contains(&fy) || (fx - 0.5).abs() > 0.3 {
+                return None;
+            }
+            let line = (x / 6) % 3 == 0 && (y / 5) % 2 == 0;
+            Some(if line { Color::new(120, 80, 40, 255) } else { Color::new(235, 215, 160, 255) })
+        })],
+    }
+}
+
+// Fantasma con la cabeza redonda, ojos y el borde de abajo ondulado según el cuadro
+fn procedural_enemy(frame: usize) -> Texture {
+    let size = TEXTURE_SIZE as f32;
+    Texture::generate_sprite(move |x, y| {
+        let (fx, fy) = (x as f32 / size, y as f32 / size);
+        let (dx, dy) = ((fx - 0.5) / 0.32, (fy - 0.42) / 0.32);
+        let head = fy < 0.42 && dx * dx + dy * dy < 1.0;
+        let phase = frame as f32 * std::f32::consts::PI;
+        let hem = 0.92 + 0.05 * (fx * 24.0 + phase).sin();
+        let body = fy >= 0.42 && fy < hem && dx.abs() < 1.0;
+        if !head && !body {
+            return None;
+        }
+        let eye = |center: f32| {
+            let (ex, ey) = ((fx - center) / 0.06, (fy - 0.4) / 0.08);
+            ex * ex + ey * ey < 1.0
+        };
+        if eye(0.4) || eye(0.6) {
+            Some(Color::new(255, 240, 80, 255))
+        } else {
+            Some(shade(Color::new(150, 40, 170, 255), 0.8 + 0.2 * (1.0 - fy)))
+        }
+    })
+}
+
+// Ruido determinista para dar algo de grano a las texturas generadas
+fn noise(x: usize, y: usize) -> f32 {
+    let mut n = (x as u32).wrapping_mul(374_761_393) ^ (y as u32).wrapping_mul(668_265_263);
+    n = (n ^ (n >> 13)).wrapping_mul(1_274_126_177);
+    (n & 0xFF) as f32 / 255.0
+}
+
+pub fn shade(color: Color, factor: f32) -> Color {
+    Color::new(
+        (color.r as f32 * factor).clamp(0.0, 255.0) as u8,
+        (color.g as f32 * factor).clamp(0.0, 255.0) as u8,
+        (color.b as f32 * factor).clamp(0.0, 255.0) as u8,
+        color.a,
+    )
+}
@@ -1,10 +1,16 @@
 use raylib::prelude::*;
-use crate::maze::{MAZE, wall_color};
+use crate::maze::{Maze, wall_color};
 use crate::player::Player;
+use crate::damage::{Damage, DamagePolicy};
+use crate::scores::{Entry, Score};
 use crate::framebuffer::Framebuffer;
+use crate::enemies::Enemy;
+use crate::pathfinding::{cell_at, Cell, Path};
+use crate::input::{confirm_pressed, key_name, menu_direction, Action, Bindings};
 
 pub fn draw_minimap_to_framebuffer(
     framebuffer: &mut Framebuffer,
+    maze: &Maze,
     player: &Player,
     block_size: i32,
     screen_width: i32,
@@ -14,7 +20,7 @@ pub fn draw_minimap_to_framebuffer(
     let minimap_size = 150;
     let minimap_x = screen_width - minimap_size - 10;
     let minimap_y = 10;
-    let mini_block = minimap_size / MAZE[0].len() as i32;
+    let mini_block = (minimap_size / maze.width().max(maze.height()).max(1) as i32).max(1);
 
     // Dibujar fondo del minimapa
     let bg_color = Color::new(66, 135, 245, 200);
@@ -27,8 +33,8 @@ pub fn draw_minimap_to_framebuffer(
     );
 
     // Dibujar celdas del laberinto
-    for (row, line) in MAZE.iter().enumerate() {
-        for (col, cell) in line.chars().enumerate() {
+    for (row, line) in maze.grid.iter().enumerate() {
+        for (col, &cell) in line.iter().enumerate() {
             let x = minimap_x + (col as i32) * mini_block;
             let y = minimap_y + (row as i32) * mini_block;
 
@@ -78,19 +84,39 @@ pub fn draw_minimap_to_framebuffer(
     framebuffer.draw_line(mini_px, mini_py, dir_x, dir_y);
 }
 
-pub fn draw_minimap(d: &mut RaylibDrawHandle, player: &Player, block_size: i32, screen_width: i32, time: f64, invulnerability_time: f64) {
+// Estado de la partida que muestra el minimapa, además del nivel y el jugador
+#[derive(Debug, Clone, Copy)]
+pub struct MinimapView<'a> {
+    pub explored: &'a [Vec<bool>],
+    pub enemies: &'a [Enemy],
+    // Camino de la pista, si se está mostrando
+    pub hint: Option<&'a Path>,
+    pub block_size: i32,
+    pub screen_width: i32,
+    pub time: f64,
+    pub invulnerability_time: f64,
+}
+
+pub fn draw_minimap(d: &mut RaylibDrawHandle, maze: &Maze, player: &Player, view: MinimapView) {
+    let MinimapView { explored, enemies, hint, block_size, screen_width, time, invulnerability_time } = view;
     let minimap_size = 150;
     let minimap_x = screen_width - minimap_size - 10;
     let minimap_y = 10;
-    let mini_block = minimap_size / MAZE[0].len() as i32;
+    let mini_block = (minimap_size / maze.width().max(maze.height()).max(1) as i32).max(1);
 
     d.draw_rectangle(minimap_x - 2, minimap_y - 2, minimap_size + 4, minimap_size + 4, Color::new(66, 135, 245, 200));
 
-    for (row, line) in MAZE.iter().enumerate() {
-        for (col, cell) in line.chars().enumerate() {
+    for (row, line) in maze.grid.iter().enumerate() {
+        for (col, &cell) in line.iter().enumerate() {
             let x = minimap_x + (col as i32) * mini_block;
             let y = minimap_y + (row as i32) * mini_block;
 
+            // Niebla sobre lo que aún no se ha visto
+            if !is_explored(explored, (row, col)) {
+                d.draw_rectangle(x, y, mini_block, mini_block, Color::new(20, 20, 30, 230));
+                continue;
+            }
+
             let color = match cell {
                 '#' => wall_color(cell),
                 'A' => wall_color(cell),
@@ -98,15 +124,41 @@ pub fn draw_minimap(d: &mut RaylibDrawHandle, player: &Player, block_size: i32,
                 'C' => wall_color(cell),
                 'D' => wall_color(cell),
                 'E' => wall_color(cell),
+                'P' | '1' | '2' | '3' => wall_color(cell),
+                'T' | 'O' => wall_color(cell),
                 _ => Color::WHITE,
             };
+            // Las puertas abiertas se ven como suelo
+            let open_door = maze.door(row, col).is_some_and(|door| !door.is_blocking());
 
-            if cell != ' ' {
+            if cell != ' ' && !open_door {
                 d.draw_rectangle(x, y, mini_block, mini_block, color);
             }
         }
     }
 
+    // Pista: camino más corto hasta la salida, solo por donde ya se ha pasado para no desvelar
+    // lo que hay bajo la niebla
+    if let Some(path) = hint {
+        let dot = (mini_block / 3).max(1);
+        for &(row, col) in path.cells.iter().filter(|&&cell| is_explored(explored, cell)) {
+            let x = minimap_x + (col as i32) * mini_block + (mini_block - dot) / 2;
+            let y = minimap_y + (row as i32) * mini_block + (mini_block - dot) / 2;
+            d.draw_rectangle(x, y, dot, dot, Color::SKYBLUE);
+        }
+    }
+
+    // Enemigos en las zonas ya vistas: rojos si persiguen al jugador, naranjas si patrullan
+    for enemy in enemies {
+        if !is_explored(explored, cell_at(enemy.x, enemy.y, block_size)) {
+            continue;
+        }
+        let x = minimap_x + (enemy.x / block_size as f32 * mini_block as f32) as i32;
+        let y = minimap_y + (enemy.y / block_size as f32 * mini_block as f32) as i32;
+        let color = if enemy.is_chasing() { Color::RED } else { Color::ORANGE };
+        d.draw_circle(x, y, (mini_block as f32 / 4.0).max(2.0), color);
+    }
+
     let mini_px = minimap_x + (player.x / block_size as f32 * mini_block as f32) as i32;
     let mini_py = minimap_y + (player.y / block_size as f32 * mini_block as f32) as i32;
 
@@ -130,28 +182,58 @@ pub fn draw_minimap(d: &mut RaylibDrawHandle, player: &Player, block_size: i32,
     );
 }
 
-pub fn draw_hud(d: &mut RaylibDrawHandle, player: &Player, invulnerability_time: f64, current_time: f64, fps: u32, screen_width: i32) {
+fn is_explored(explored: &[Vec<bool>], (row, col): Cell) -> bool {
+    explored.get(row).and_then(|line| line.get(col)).copied().unwrap_or(false)
+}
+
+// Estado de la partida que muestra el HUD, además del jugador, el daño y los controles
+#[derive(Debug, Clone, Copy)]
+pub struct HudView {
+    pub invulnerability_time: f64,
+    pub speed_boost_time: f64,
+    // Monedas recogidas y las del nivel, si tiene alguna
+    pub coins: Option<(u32, u32)>,
+    pub current_time: f64,
+    pub fps: u32,
+    pub screen_width: i32,
+}
+
+pub fn draw_hud(d: &mut RaylibDrawHandle, player: &Player, damage: &Damage, bindings: &Bindings, view: HudView) {
+    let HudView { invulnerability_time, speed_boost_time, coins, current_time, fps, screen_width } = view;
     d.draw_rectangle(5, 5, 350, 150, Color::new(0, 0, 0, 150));
-    d.draw_text("VIDAS:", 15, 15, 20, Color::WHITE);
+    let label = if damage.policy == DamagePolicy::HealthBar { "SALUD:" } else { "VIDAS:" };
+    d.draw_text(label, 15, 15, 20, Color::WHITE);
 
-    for i in 0..player.max_lives {
+    // Cada corazón vale 1 de salud; con la barra de salud el último puede estar a medias
+    let hearts = player.max_health.ceil() as i32;
+    for i in 0..hearts {
         let heart_x = 90 + i * 35;
         let heart_y = 25;
         let heart_size = 20;
-        if i < player.lives {
+        let fill = (player.health - i as f32).clamp(0.0, 1.0);
+        if fill >= 1.0 {
             draw_heart(d, heart_x, heart_y, heart_size, Color::RED);
         } else {
             draw_heart(d, heart_x, heart_y, heart_size, Color::new(80, 80, 80, 255));
+            if fill > 0.0 {
+                draw_partial_heart(d, heart_x, heart_y, heart_size, fill, Color::RED);
+            }
         }
     }
 
-    let lives_color = match player.lives {
-        3 => Color::GREEN,
-        2 => Color::YELLOW,
-        1 => Color::RED,
+    let lives_color = match player.health {
+        n if n >= 3.0 => Color::GREEN,
+        n if n >= 2.0 => Color::YELLOW,
+        n if n > 0.0 => Color::RED,
         _ => Color::GRAY,
     };
-    d.draw_text(&format!("{}/{}", player.lives, player.max_lives), 200, 15, 20, lives_color);
+    let count_x = 200.max(90 + hearts * 35 - 10);
+    d.draw_text(&format!("{}/{}", format_health(player.health), format_health(player.max_health)), count_x, 15, 20, lives_color);
+
+    // Llaves recogidas
+    for (i, key) in player.inventory.keys.iter().enumerate() {
+        draw_key_icon(d, 200 + i as i32 * 30, 47, key.color());
+    }
 
     if invulnerability_time > 0.0 {
         let blink = (current_time * 6.0) as i32 % 2 == 0;
@@ -160,22 +242,335 @@ pub fn draw_hud(d: &mut RaylibDrawHandle, player: &Player, invulnerability_time:
         }
     }
 
-    d.draw_text("WASD: Mover | Mouse: Mirar", 15, 70, 14, Color::WHITE);
-    d.draw_text("¡CUIDADO! Pierdes vida al chocar", 15, 90, 12, Color::ORANGE);
+    let help = format!(
+        "{}: Mover | {}/Mouse: Girar | {}: {} | {}: {}",
+        keys_text(bindings, &[Action::MoveForward, Action::StrafeLeft, Action::MoveBack, Action::StrafeRight]),
+        keys_text(bindings, &[Action::TurnLeft, Action::TurnRight]),
+        keys_text(bindings, &[Action::Interact]),
+        Action::Interact.label(),
+        keys_text(bindings, &[Action::Pause]),
+        Action::Pause.label(),
+    );
+    d.draw_text(&help, 15, 70, 14, Color::WHITE);
+    let warning = match damage.policy {
+        DamagePolicy::Lives => "¡CUIDADO! Pierdes vida al chocar".to_string(),
+        DamagePolicy::HealthBar => "¡CUIDADO! Los choques fuertes quitan más salud".to_string(),
+        DamagePolicy::HazardOnly => format!("¡CUIDADO! Quitan vida las paredes {}", damage.hazard_names()),
+        DamagePolicy::Zen => "Modo zen: los choques no hacen daño".to_string(),
+    };
+    d.draw_text(&warning, 15, 90, 12, Color::ORANGE);
     d.draw_text("Paredes:", 15, 110, 12, Color::WHITE);
     d.draw_text("A: Verde | B: Azul | C: Amarillo | D: Magenta", 15, 125, 10, Color::LIGHTGRAY);
 
     let fps_color = if fps > 30 { Color::GREEN } else if fps > 15 { Color::YELLOW } else { Color::RED };
     d.draw_text(&format!("FPS: {}", fps), 15, 145, 16, fps_color);
+    if let Some((collected, total)) = coins {
+        d.draw_text(&format!("MONEDAS: {}/{}", collected, total), 110, 145, 16, Color::GOLD);
+    }
+    if speed_boost_time > 0.0 {
+        d.draw_text(&format!("VELOZ {:.0} s", speed_boost_time.ceil()), 260, 145, 16, Color::SKYBLUE);
+    }
 
-    if player.lives == 1 {
+    if player.is_alive() && player.health <= 1.0 && damage.policy != DamagePolicy::Zen {
         let alpha = ((current_time * 3.0).sin() * 0.5 + 0.5) * 255.0;
         let alpha = alpha.clamp(0.0, 255.0) as u8;
         d.draw_text("¡ÚLTIMA VIDA!", screen_width / 2 - 100, 50, 30, Color::new(255, 0, 0, alpha));
     }
 }
 
+// Llave pequeña para el HUD: anilla y caña con un diente
+fn draw_key_icon(d: &mut RaylibDrawHandle, x: i32, y: i32, color: Color) {
+    d.draw_circle_lines(x + 5, y + 5, 5.0, color);
+    d.draw_rectangle(x + 10, y + 4, 12, 3, color);
+    d.draw_rectangle(x + 18, y + 7, 3, 4, color);
+}
+
+// Aviso de la partida centrado en la parte baja de la pantalla
+pub fn draw_message(d: &mut RaylibDrawHandle, message: &str, screen_width: i32, screen_height: i32) {
+    let font_size = 24;
+    let width = measure_text(message, font_size);
+    let (x, y) = ((screen_width - width) / 2, screen_height - 80);
+    d.draw_rectangle(x - 12, y - 8, width + 24, font_size + 16, Color::new(0, 0, 0, 170));
+    d.draw_text(message, x, y, font_size, Color::GOLD);
+}
+
 pub fn draw_heart(d: &mut RaylibDrawHandle, x: i32, y: i32, size: i32, color: Color) {
     let radius = size as f32 / 2.0;
     d.draw_circle(x, y, radius, color);
-}
\ No newline at end of file
+}
+
+// Corazón lleno en la fracción `fill` (0..1): un sector que empieza arriba y gira como el reloj
+pub fn draw_partial_heart(d: &mut RaylibDrawHandle, x: i32, y: i32, size: i32, fill: f32, color: Color) {
+    let radius = size as f32 / 2.0;
+    d.draw_circle_sector(Vector2::new(x as f32, y as f32), radius, 270.0, 270.0 + 360.0 * fill, 24, color);
+}
+
+// Teclas asignadas a varias acciones, separadas por barras ("W/S"); "-" si alguna no tiene tecla
+fn keys_text(bindings: &Bindings, actions: &[Action]) -> String {
+    let names: Vec<&str> = actions.iter().map(|&action| bindings.key(action).map_or("-", key_name)).collect();
+    names.join("/")
+}
+
+// Salud sin decimales si es entera ("3"), con uno si no ("2.5")
+fn format_health(health: f32) -> String {
+    if health.fract() == 0.0 { format!("{:.0}", health) } else { format!("{:.1}", health) }
+}
+
+// Pantalla de instrucciones
+pub fn draw_title_screen(d: &mut RaylibDrawHandle, par: Option<f32>, damage: &Damage, bindings: &Bindings, high_scores: &[Entry], level_label: Option<&str>) {
+    d.clear_background(Color::DARKBLUE);
+    d.draw_text("LABERINTO 3D RAYCASTING", 80, 80, 40, Color::YELLOW);
+    if let Some(label) = level_label {
+        d.draw_text(label, 80, 125, 22, Color::SKYBLUE);
+    }
+    d.draw_text("Controles:", 120, 160, 30, Color::WHITE);
+    let pairs = [
+        (Action::MoveForward, Action::MoveBack),
+        (Action::StrafeLeft, Action::StrafeRight),
+        (Action::TurnLeft, Action::TurnRight),
+    ];
+    let mut controls: Vec<(String, String)> = pairs
+        .iter()
+        .map(|&(first, second)| (keys_text(bindings, &[first, second]), format!("{} / {}", first.label(), second.label())))
+        .collect();
+    controls.push(("Mouse".to_string(), "Mirar alrededor".to_string()));
+    controls.push((keys_text(bindings, &[Action::Pause]), Action::Pause.label().to_string()));
+    for (i, (keys, label)) in controls.iter().enumerate() {
+        d.draw_text(&format!("- {}: {}", keys, label), 140, 195 + i as i32 * 25, 22, Color::LIGHTGRAY);
+    }
+    let lives = format_health(damage.max_health);
+    let (title, rules) = match damage.policy {
+        DamagePolicy::Lives => (
+            "Sistema de Vidas",
+            [format!("- Tienes {} vidas", lives), "- Pierdes 1 vida al chocar con paredes".to_string(), "- Sin vidas = Game Over".to_string()],
+        ),
+        DamagePolicy::HealthBar => (
+            "Barra de Salud",
+            [format!("- Tienes {} corazones de salud", lives), "- Cuanto más fuerte chocas, más salud pierdes".to_string(), "- Sin salud = Game Over".to_string()],
+        ),
+        DamagePolicy::HazardOnly => (
+            "Paredes Peligrosas",
+            [format!("- Tienes {} vidas", lives), format!("- Solo quitan vida las paredes {}", damage.hazard_names()), "- Sin vidas = Game Over".to_string()],
+        ),
+        DamagePolicy::Zen => (
+            "Modo Zen",
+            ["- Los choques no hacen daño".to_string(), "- Recorre el laberinto sin prisa".to_string(), "- No hay Game Over".to_string()],
+        ),
+    };
+    d.draw_text(title, 120, 340, 28, Color::RED);
+    for (i, rule) in rules.iter().enumerate() {
+        d.draw_text(rule, 140, 370 + i as i32 * 20, 20, Color::LIGHTGRAY);
+    }
+    d.draw_text("NUEVO: Paredes con colores diferentes", 80, 430, 18, Color::ORANGE);
+    d.draw_text("Objetivo: Encuentra la salida marcada", 80, 450, 18, Color::LIME);
+    if let Some(par) = par {
+        d.draw_text(&format!("Tiempo par: {:.1} s", par), 80, 470, 18, Color::SKYBLUE);
+    }
+    d.draw_text("Presiona ENTER para comenzar", 120, 500, 30, Color::GREEN);
+    // Elegir nivel solo tiene sentido en la campaña
+    let help = if level_label.is_some() { "N: Elegir nivel | C: Cargar partida | O: Ajustes | ESC: Salir" } else { "C: Cargar partida | O: Ajustes | ESC: Salir" };
+    d.draw_text(help, 120, 540, 20, Color::LIGHTGRAY);
+    draw_high_scores(d, high_scores, None, 640, 160);
+}
+
+pub fn draw_game_over_screen(d: &mut RaylibDrawHandle) {
+    d.clear_background(Color::MAROON);
+    d.draw_text("GAME OVER", 200, 180, 60, Color::RED);
+    d.draw_text("¡Te quedaste sin vidas!", 180, 260, 30, Color::WHITE);
+    d.draw_text("¡Fuiste demasiado descuidado!", 150, 300, 24, Color::LIGHTGRAY);
+    d.draw_text("Presiona ENTER para reiniciar", 140, 380, 30, Color::YELLOW);
+}
+
+pub fn draw_level_complete_screen(d: &mut RaylibDrawHandle, score: &Score, rank: Option<usize>, high_scores: &[Entry], next_label: &str) {
+    d.clear_background(Color::DARKBLUE);
+    d.draw_text("¡FELICIDADES!", 200, 120, 50, Color::YELLOW);
+    d.draw_text("¡Has completado el laberinto!", 150, 190, 30, Color::LIME);
+
+    // Desglose de la puntuación: concepto a la izquierda y puntos a la derecha
+    let rows = [
+        (format!("Tiempo: {:.1} s", score.time), score.time_points),
+        (format!("Vidas restantes: {}", format_health(score.health)), score.health_points),
+        (format!("Monedas recogidas: {}", score.collected), score.collectible_points),
+        (format!("Eficiencia del recorrido: {:.0}%", score.efficiency * 100.0), score.efficiency_points),
+    ];
+    for (i, (label, points)) in rows.iter().enumerate() {
+        let y = 250 + i as i32 * 30;
+        d.draw_text(label, 170, y, 22, Color::WHITE);
+        d.draw_text(&format!("+{}", points), 520, y, 22, Color::GOLD);
+    }
+    d.draw_text(&format!("PUNTUACIÓN: {}", score.total()), 170, 380, 30, Color::YELLOW);
+    match rank {
+        Some(0) => d.draw_text("¡Nuevo récord!", 170, 415, 22, Color::LIME),
+        Some(rank) => d.draw_text(&format!("Puesto {} en la tabla", rank + 1), 170, 415, 22, Color::SKYBLUE),
+        None => {}
+    }
+
+    draw_high_scores(d, high_scores, rank, 640, 250);
+    d.draw_text(next_label, 140, 470, 30, Color::WHITE);
+}
+
+// Tabla de mejores puntuaciones del nivel; `highlight` marca el puesto recién conseguido
+pub fn draw_high_scores(d: &mut RaylibDrawHandle, entries: &[Entry], highlight: Option<usize>, x: i32, y: i32) {
+    d.draw_text("Mejores puntuaciones", x, y, 22, Color::GOLD);
+    if entries.is_empty() {
+        d.draw_text("Aún no hay ninguna", x, y + 35, 18, Color::LIGHTGRAY);
+        return;
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let color = if highlight == Some(i) { Color::LIME } else { Color::LIGHTGRAY };
+        let row_y = y + 35 + i as i32 * 24;
+        d.draw_text(&format!("{}. {}", i + 1, entry.points), x, row_y, 20, color);
+        d.draw_text(&format!("{:.1} s", entry.time), x + 150, row_y, 20, color);
+    }
+}
+
+// Menú vertical que se maneja con teclado (flechas/W/S y ENTER), mando o ratón.
+// Si hay más opciones que `max_visible` se desplaza para mostrar la seleccionada.
+pub struct Menu {
+    pub items: Vec<String>,
+    pub selected: usize,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub item_height: i32,
+    pub font_size: i32,
+    pub max_visible: usize,
+    scroll: usize,
+}
+
+impl Menu {
+    pub fn new(items: &[&str], x: i32, y: i32) -> Self {
+        Self {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            selected: 0,
+            x,
+            y,
+            width: 360,
+            item_height: 50,
+            font_size: 24,
+            max_visible: usize::MAX,
+            scroll: 0,
+        }
+    }
+
+    fn visible_items(&self) -> std::ops::Range<usize> {
+        self.scroll..self.items.len().min(self.scroll.saturating_add(self.max_visible))
+    }
+
+    // Altura ocupada por las opciones visibles
+    pub fn height(&self) -> i32 {
+        self.visible_items().len() as i32 * self.item_height
+    }
+
+    pub fn scroll_to_selected(&mut self) {
+        let visible = self.max_visible.max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + visible {
+            self.scroll = self.selected + 1 - visible;
+        }
+        self.scroll = self.scroll.min(self.items.len().saturating_sub(visible));
+    }
+
+    pub fn item_rect(&self, index: usize) -> Rectangle {
+        let row = index as i32 - self.scroll as i32;
+        Rectangle::new(
+            self.x as f32,
+            (self.y + row * self.item_height) as f32,
+            self.width as f32,
+            (self.item_height - 8) as f32,
+        )
+    }
+
+    // Devuelve el índice de la opción activada en este fotograma, si la hay
+    pub fn update(&mut self, rl: &RaylibHandle) -> Option<usize> {
+        let count = self.items.len();
+        if count == 0 {
+            return None;
+        }
+        self.selected = (self.selected as i32 + menu_direction(rl)).rem_euclid(count as i32) as usize;
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel != 0.0 {
+            let step = if wheel < 0.0 { 1 } else { count - 1 };
+            self.selected = (self.selected + step) % count;
+        }
+        self.scroll_to_selected();
+
+        let mouse = rl.get_mouse_position();
+        let hovered = self.visible_items().find(|&index| self.item_rect(index).check_collision_point_rec(mouse));
+        if let Some(index) = hovered {
+            if rl.get_mouse_delta().length() > 0.0 {
+                self.selected = index;
+            }
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.selected = index;
+                return Some(index);
+            }
+        }
+
+        if confirm_pressed(rl) {
+            return Some(self.selected);
+        }
+        None
+    }
+
+    pub fn draw(&self, d: &mut RaylibDrawHandle) {
+        for index in self.visible_items() {
+            let item = &self.items[index];
+            let rect = self.item_rect(index);
+            let selected = index == self.selected;
+            let background = if selected { Color::new(66, 135, 245, 220) } else { Color::new(0, 0, 0, 160) };
+            d.draw_rectangle_rec(rect, background);
+            let text_color = if selected { Color::YELLOW } else { Color::WHITE };
+            let text_y = rect.y as i32 + (rect.height as i32 - self.font_size) / 2;
+            d.draw_text(item, rect.x as i32 + 20, text_y, self.font_size, text_color);
+        }
+        // Flechas que indican que hay más opciones fuera de la vista
+        let arrow_x = self.x + self.width + 10;
+        if self.scroll > 0 {
+            d.draw_text("^", arrow_x, self.y, 20, Color::LIGHTGRAY);
+        }
+        if self.visible_items().end < self.items.len() {
+            d.draw_text("v", arrow_x, self.y + self.height() - 24, 20, Color::LIGHTGRAY);
+        }
+    }
+}
+
+// Se dibuja encima del último fotograma de la partida
+pub fn draw_pause_screen(d: &mut RaylibDrawHandle, menu: &Menu, screen_width: i32, screen_height: i32) {
+    d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 150));
+    d.draw_text("PAUSA", menu.x + 90, menu.y - 90, 60, Color::YELLOW);
+    menu.draw(d);
+    d.draw_text("ESC: Continuar", menu.x + 90, menu.y + menu.height() + 10, 20, Color::LIGHTGRAY);
+}
+
+pub fn draw_level_select_screen(d: &mut RaylibDrawHandle, menu: &Menu) {
+    d.clear_background(Color::DARKBLUE);
+    d.draw_text("ELEGIR NIVEL", menu.x, 60, 40, Color::YELLOW);
+    menu.draw(d);
+    let help_y = menu.y + menu.height() + 10;
+    d.draw_text("Completa un nivel para desbloquear el siguiente | ESC: volver", menu.x, help_y, 20, Color::LIGHTGRAY);
+}
+
+pub fn draw_save_slots_screen(d: &mut RaylibDrawHandle, menu: &Menu, title: &str, message: Option<&str>) {
+    d.clear_background(Color::DARKBLUE);
+    d.draw_text(title, menu.x, 60, 40, Color::YELLOW);
+    menu.draw(d);
+    let help_y = menu.y + menu.height() + 10;
+    if let Some(message) = message {
+        d.draw_text(message, menu.x, help_y, 20, Color::GOLD);
+    }
+    d.draw_text("ENTER: elegir ranura | ESC: volver", menu.x, help_y + 30, 20, Color::LIGHTGRAY);
+}
+
+pub fn draw_settings_screen(d: &mut RaylibDrawHandle, menu: &Menu, waiting_for_key: bool) {
+    d.clear_background(Color::DARKBLUE);
+    d.draw_text("AJUSTES", menu.x, 30, 40, Color::YELLOW);
+    menu.draw(d);
+    let help_y = menu.y + menu.height() + 6;
+    if waiting_for_key {
+        d.draw_text("Pulsa la nueva tecla (ESC: cancelar)", menu.x, help_y, 20, Color::GOLD);
+    } else {
+        d.draw_text("IZQ/DER o clic: cambiar | ENTER en un control: nueva tecla | ESC: volver", menu.x, help_y, 20, Color::LIGHTGRAY);
+    }
+}
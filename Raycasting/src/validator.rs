@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+use std::fmt;
+use crate::doors::KeyColor;
+use crate::maze::{Maze, CELL_CHARS, is_wall_char};
+use crate::pathfinding::{find_path, is_passable, neighbors, Algorithm, Cell};
+use crate::teleporters::{PORTAL_CHAR, TELEPORTER_CHAR};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    UnequalRowLength { expected: usize, found: usize },
+    UnknownCell(char),
+    OpenBorder,
+    NoExit,
+    MultipleExits,
+    UnreachableExit,
+    BlockedStart,
+    DecorationInWall,
+    KeyInWall,
+    PickupInWall,
+    // Puerta cerrada con una llave que no está en el nivel
+    MissingKey(KeyColor),
+    // Puerta cerrada con una llave a la que no se llega sin haberla abierto antes
+    UnreachableKey(KeyColor),
+    EnemyInWall,
+    // Punto de la ruta de un enemigo al que no se llega desde donde empieza
+    UnreachableWaypoint,
+    // Celda `T` u `O` sin línea `teleporte` o `portal` que diga a dónde lleva
+    UnlinkedTeleporter,
+    TeleporterIntoWall,
+}
+
+// Problema encontrado en el nivel; fila y columna empiezan en 0 como en la cabecera del nivel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub row: usize,
+    pub col: usize,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {}): ", self.row, self.col)?;
+        match &self.kind {
+            IssueKind::UnequalRowLength { expected, found } => {
+                write!(f, "la fila mide {} celdas, se esperaban {}", found, expected)
+            }
+            IssueKind::UnknownCell(c) => write!(f, "carácter de celda desconocido `{}`", c),
+            IssueKind::OpenBorder => write!(f, "hueco en el borde exterior"),
+            IssueKind::NoExit => write!(f, "el nivel no tiene salida `E`"),
+            IssueKind::MultipleExits => write!(f, "salida `E` adicional"),
+            IssueKind::UnreachableExit => write!(f, "la salida no es alcanzable desde el inicio"),
+            IssueKind::BlockedStart => write!(f, "la celda de inicio no es transitable"),
+            IssueKind::DecorationInWall => write!(f, "decoración dentro de un muro o fuera del mapa"),
+            IssueKind::KeyInWall => write!(f, "llave dentro de un muro, en una puerta o fuera del mapa"),
+            IssueKind::PickupInWall => write!(f, "objeto dentro de un muro, en una puerta o fuera del mapa"),
+            IssueKind::MissingKey(color) => write!(f, "puerta cerrada con la llave {}, que no está en el nivel", color.name()),
+            IssueKind::UnreachableKey(color) => write!(f, "puerta cerrada con la llave {}, a la que no se llega sin abrirla", color.name()),
+            IssueKind::EnemyInWall => write!(f, "ruta de enemigo dentro de un muro, en una puerta o fuera del mapa"),
+            IssueKind::UnreachableWaypoint => write!(f, "el enemigo no puede llegar a este punto de su ruta"),
+            IssueKind::UnlinkedTeleporter => write!(f, "teletransporte sin destino"),
+            IssueKind::TeleporterIntoWall => write!(f, "el teletransporte lleva a un muro, una puerta o fuera del mapa"),
+        }
+    }
+}
+
+pub fn validate(maze: &Maze) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let height = maze.height();
+    let expected = maze.grid.first().map(|row| row.len()).unwrap_or(0);
+
+    for (row, line) in maze.grid.iter().enumerate() {
+        if line.len() != expected {
+            issues.push(Issue {
+                row,
+                col: line.len().min(expected),
+                kind: IssueKind::UnequalRowLength { expected, found: line.len() },
+            });
+        }
+        for (col, &cell) in line.iter().enumerate() {
+            if !CELL_CHARS.contains(&cell) {
+                issues.push(Issue { row, col, kind: IssueKind::UnknownCell(cell) });
+            }
+            // La salida en el borde es válida: el rayo se detiene en ella igual que en un muro
+            let on_border = row == 0 || row + 1 == height || col == 0 || col + 1 == line.len();
+            if on_border && !is_wall_char(cell) && cell != 'E' {
+                issues.push(Issue { row, col, kind: IssueKind::OpenBorder });
+            }
+            if (cell == TELEPORTER_CHAR || cell == PORTAL_CHAR) && maze.teleporter(row, col).is_none() {
+                issues.push(Issue { row, col, kind: IssueKind::UnlinkedTeleporter });
+            }
+        }
+    }
+
+    let (start_row, start_col) = maze.start;
+    if maze.is_wall_cell(start_row, start_col) {
+        issues.push(Issue { row: start_row, col: start_col, kind: IssueKind::BlockedStart });
+    }
+
+    for decoration in &maze.decorations {
+        if maze.is_wall_cell(decoration.row, decoration.col) {
+            issues.push(Issue { row: decoration.row, col: decoration.col, kind: IssueKind::DecorationInWall });
+        }
+    }
+
+    for key in &maze.keys {
+        if maze.is_wall_cell(key.row, key.col) {
+            issues.push(Issue { row: key.row, col: key.col, kind: IssueKind::KeyInWall });
+        }
+    }
+    for pickup in &maze.pickups {
+        if maze.is_wall_cell(pickup.row, pickup.col) {
+            issues.push(Issue { row: pickup.row, col: pickup.col, kind: IssueKind::PickupInWall });
+        }
+    }
+    let (reachable, collected) = reachable_with_keys(maze);
+    for door in &maze.doors {
+        let Some(lock) = door.lock else {
+            continue;
+        };
+        if !maze.keys.iter().any(|key| key.color == lock) {
+            issues.push(Issue { row: door.row, col: door.col, kind: IssueKind::MissingKey(lock) });
+        } else if !collected.contains(&lock) {
+            issues.push(Issue { row: door.row, col: door.col, kind: IssueKind::UnreachableKey(lock) });
+        }
+    }
+
+    for teleporter in &maze.teleporters {
+        let (row, col) = teleporter.from;
+        if maze.is_wall_cell(teleporter.to.0, teleporter.to.1) {
+            issues.push(Issue { row, col, kind: IssueKind::TeleporterIntoWall });
+        }
+    }
+
+    for patrol in &maze.enemies {
+        let mut blocked = false;
+        for &(row, col) in patrol {
+            if maze.is_wall_cell(row, col) {
+                issues.push(Issue { row, col, kind: IssueKind::EnemyInWall });
+                blocked = true;
+            }
+        }
+        if blocked {
+            continue;
+        }
+        for &(row, col) in &patrol[1..] {
+            if find_path(maze, patrol[0], (row, col), Algorithm::Bfs).is_none() {
+                issues.push(Issue { row, col, kind: IssueKind::UnreachableWaypoint });
+            }
+        }
+    }
+
+    let exits: Vec<(usize, usize)> = maze
+        .grid
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            line.iter()
+                .enumerate()
+                .filter(|(_, c)| **c == 'E')
+                .map(move |(col, _)| (row, col))
+        })
+        .collect();
+
+    match exits.len() {
+        0 => issues.push(Issue { row: start_row, col: start_col, kind: IssueKind::NoExit }),
+        _ => {
+            for &(row, col) in &exits[1..] {
+                issues.push(Issue { row, col, kind: IssueKind::MultipleExits });
+            }
+        }
+    }
+
+    for &(row, col) in &exits {
+        if !reachable[row][col] {
+            issues.push(Issue { row, col, kind: IssueKind::UnreachableExit });
+        }
+    }
+
+    issues
+}
+
+// Celdas a las que llega el jugador desde el inicio y colores de llave que puede recoger. Las
+// puertas con llave empiezan cerradas y se abren al alcanzar una llave de su color, así que se
+// repite la búsqueda hasta que no aparecen llaves nuevas
+fn reachable_with_keys(maze: &Maze) -> (Vec<Vec<bool>>, Vec<KeyColor>) {
+    let mut collected: Vec<KeyColor> = Vec::new();
+    loop {
+        let reachable = reachable_cells(maze, &collected);
+        let before = collected.len();
+        for key in &maze.keys {
+            let in_reach = reachable.get(key.row).and_then(|line| line.get(key.col)).copied().unwrap_or(false);
+            if in_reach && !collected.contains(&key.color) {
+                collected.push(key.color);
+            }
+        }
+        if collected.len() == before {
+            return (reachable, collected);
+        }
+    }
+}
+
+// Búsqueda en anchura desde el inicio que no cruza las puertas cuya llave no está en `keys`
+fn reachable_cells(maze: &Maze, keys: &[KeyColor]) -> Vec<Vec<bool>> {
+    let mut reachable = vec![vec![false; maze.width()]; maze.height()];
+    if !is_passable(maze, maze.start) {
+        return reachable;
+    }
+    let opens = |(row, col): Cell| maze.door(row, col).and_then(|door| door.lock).is_none_or(|lock| keys.contains(&lock));
+    let mut queue = VecDeque::from([maze.start]);
+    reachable[maze.start.0][maze.start.1] = true;
+    while let Some(current) = queue.pop_front() {
+        for next in neighbors(maze, current) {
+            if !reachable[next.0][next.1] && opens(next) {
+                reachable[next.0][next.1] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(text: &str) -> Vec<Issue> {
+        validate(&Maze::parse(text).unwrap())
+    }
+
+    // Dos puertas seguidas: la llave verde está detrás de la roja
+    const CHAINED_DOORS: &str = "\
+inicio = 2 1
+llave = 1 1 roja
+llave = 1 4 verde
+---
+#########
+#  #  # #
+#  1  2 E
+#  #  # #
+#########
+";
+
+    #[test]
+    fn doors_open_in_order_as_their_keys_are_reached() {
+        assert_eq!(issues(CHAINED_DOORS), Vec::new());
+    }
+
+    #[test]
+    fn key_behind_its_own_door_is_reported() {
+        let text = CHAINED_DOORS.replace("llave = 1 4 verde", "llave = 1 7 verde");
+        assert_eq!(
+            issues(&text),
+            vec![
+                Issue { row: 2, col: 6, kind: IssueKind::UnreachableKey(KeyColor::Green) },
+                Issue { row: 2, col: 8, kind: IssueKind::UnreachableExit },
+            ]
+        );
+    }
+
+    #[test]
+    fn key_behind_another_locked_door_is_reported() {
+        // Cada llave está detrás de la puerta de la otra
+        let text = "inicio = 2 1\nllave = 2 4 verde\nllave = 2 7 roja\n---\n#########\n#  #  # #\n#  1  2 E\n#  #  # #\n#########\n";
+        let kinds: Vec<IssueKind> = issues(text).into_iter().map(|issue| issue.kind).collect();
+        assert!(kinds.contains(&IssueKind::UnreachableKey(KeyColor::Red)));
+        assert!(kinds.contains(&IssueKind::UnreachableKey(KeyColor::Green)));
+        assert!(kinds.contains(&IssueKind::UnreachableExit));
+    }
+
+    #[test]
+    fn locked_door_without_key_is_reported() {
+        let text = CHAINED_DOORS.replace("llave = 1 4 verde\n", "");
+        assert_eq!(
+            issues(&text),
+            vec![
+                Issue { row: 2, col: 6, kind: IssueKind::MissingKey(KeyColor::Green) },
+                Issue { row: 2, col: 8, kind: IssueKind::UnreachableExit },
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable_enemy_waypoints_are_reported() {
+        let text = "enemigo = 1 1, 1 3, 3 2\n---\n#####\n#   #\n#####\n#  E#\n#####\n";
+        let waypoints: Vec<Issue> =
+            issues(text).into_iter().filter(|issue| issue.kind == IssueKind::UnreachableWaypoint).collect();
+        assert_eq!(waypoints, vec![Issue { row: 3, col: 2, kind: IssueKind::UnreachableWaypoint }]);
+    }
+
+    #[test]
+    fn teleporter_cells_need_a_destination() {
+        assert_eq!(
+            issues("---\n#####\n# T #\n#  E#\n#####\n"),
+            vec![Issue { row: 1, col: 2, kind: IssueKind::UnlinkedTeleporter }]
+        );
+    }
+
+    #[test]
+    fn teleporters_into_walls_are_reported() {
+        assert_eq!(
+            issues("teleporte = 1 2 -> 0 0 0\n---\n#####\n# T #\n#  E#\n#####\n"),
+            vec![Issue { row: 1, col: 2, kind: IssueKind::TeleporterIntoWall }]
+        );
+    }
+}